@@ -31,6 +31,8 @@
 | 26 | `FOR WITHOUT NEXT` |
 | 29 | `WHILE WITHOUT WEND` |
 | 30 | `WEND WITHOUT WHILE` |
+| 31 | `LOOP WITHOUT DO` |
+| 32 | `DO WITHOUT LOOP` |
 | 50 | `FIELD OVERFLOW` |
 | 51 | `INTERNAL ERROR` |
 | 52 | `BAD FILE NUMBER` |
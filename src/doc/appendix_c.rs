@@ -10,6 +10,10 @@ notorious for being slow.
 
 64K BASIC is a compiler. Lexical analysis is crippled to mimic
 ROM BASIC but after that it parses to a nice abstract syntax tree.
+Keywords are found even inside names so `BANDS` is `B AND S`.
+The words `DO`, `EXIT`, `LOOP`, `UNTIL`, and `WAIT` are not found
+inside names like the others. They were not keywords in ROM BASIC so
+names like `DOT` and `WAITING` still work. `DO` by itself is reserved.
 The syntax tree is compiled into link objects. The link objects
 contain opcodes which are resolved into a program for a virtual
 machine. The virtual machine is custom for 64K BASIC.
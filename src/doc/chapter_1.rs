@@ -43,16 +43,20 @@ If you don't decorate a literal number, it will be assigned an appropriate type.
  * If the number fits into an Integer (-32767 to 32767), it is an Integer.
  * Anything that doesn't match the above is a Single.
 
+When printed in exponent form, a Single uses E and a Double uses D.
+
 You can't have -32768 as a literal Integer although you can store -32768
 as the result of an expression into an Integer variable. This is one of
 many quirks of BASIC that 64K BASIC preserves.
 
-Integers may also be specified in hexadecimal or octal with the "&" decorator.
+Integers may also be specified in hexadecimal, octal, or binary with
+the "&" decorator.
 
 ```text
-&10  ' Octal for 8
-&010 ' Octal for 8
-&H0D ' Hex for 13
+&10    ' Octal for 8
+&010   ' Octal for 8
+&H0D   ' Hex for 13
+&B1010 ' Binary for 10
 ```
 
 Values are promoted as needed to preserve precision. For example, adding
@@ -63,6 +63,9 @@ pub mod DELETE;
 #[path = "statements/dim.rs"]
 #[allow(non_snake_case)]
 pub mod DIM;
+#[path = "statements/do.rs"]
+#[allow(non_snake_case)]
+pub mod DO;
 
 #[path = "statements/end.rs"]
 #[allow(non_snake_case)]
@@ -71,6 +74,9 @@ pub mod END;
 #[path = "statements/erase.rs"]
 #[allow(non_snake_case)]
 pub mod ERASE;
+#[path = "statements/exit.rs"]
+#[allow(non_snake_case)]
+pub mod EXIT;
 
 #[path = "statements/for.rs"]
 #[allow(non_snake_case)]
@@ -160,6 +166,10 @@ pub mod SWAP;
 #[allow(non_snake_case)]
 pub mod TRON;
 
+#[path = "statements/wait.rs"]
+#[allow(non_snake_case)]
+pub mod WAIT;
+
 #[path = "statements/while.rs"]
 #[allow(non_snake_case)]
 pub mod WHILE;
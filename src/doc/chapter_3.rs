@@ -59,7 +59,8 @@ pub mod CHR {
 
 pub mod CINT {
     /*!
-    ## `CINT(X)` Returns X as an Integer. Overflow error may occur.
+    ## `CINT(X)` Returns X rounded to the nearest Integer. Halves round to even.
+    Overflow error may occur.
     ```text
     PRINT CINT(-9.9)
     -10
@@ -90,6 +91,7 @@ pub mod CSNG {
 pub mod DATE {
     /*!
     ## `DATE$` Returns the system date.
+    Assign `DATE$="MM-DD-YYYY"` to change the date seen by the program.
     ```text
     PRINT DATE$
     12-31-2000
@@ -121,6 +123,8 @@ pub mod FIX {
 pub mod HEX {
     /*!
     ## `HEX$(X)` Returns hexadecimal string of integer X.
+    X may be from -32768 to 65535. Negative numbers are shown as 16-bit
+    two's complement, so `&HFFFF` is -1.
     ```text
     PRINT HEX$(-1)
     FFFF
@@ -185,6 +189,7 @@ pub mod LEN {
 pub mod LOG {
     /*!
     ## `LOG(X)` Returns the natural logarithm of X.
+    X must be greater than zero.
     ```text
     PRINT LOG(8/37)
     -1.5314764
@@ -241,7 +246,9 @@ pub mod POS {
 pub mod RND {
     /*!
     ## `RND(X)` Returns a pseudo-random number.
-    Wichman-Hill Random Number Generator.
+    Wichman-Hill Random Number Generator. Hosts may select the GW-BASIC
+    linear congruential generator instead so ported programs repeat
+    their original sequences.
     Returns a random Single between 0 and 1 when X is missing or > 0.
     When X is 0, return the previous random number.
     When X < 0 the random number generator is seeded with X.
@@ -287,6 +294,7 @@ pub mod SPC {
 pub mod SQR {
     /*!
     ## `SQR(X)` Returns the square root of X.
+    X must not be negative.
     ```text
     PRINT SQR(5)
      2.236068
@@ -307,8 +315,7 @@ pub mod STR {
 pub mod STRING {
     /*!
     ## `STRING$(X, <Y|Y$>)` Returns X copies of Y as a string.
-    You can specify Y as an integer or a string. Only the first
-    character of a string is used.
+    You can specify Y as an integer or a single character string.
     ```text
     PRINT STRING$(5,45)"KAPOW"STRING$(5,"-")
     -----KAPOW-----
@@ -342,6 +349,7 @@ pub mod TAN {
 pub mod TIME {
     /*!
     ## `TIME$` Returns the system time.
+    Assign `TIME$="HH:MM:SS"` to change the time seen by the program.
     ```text
     PRINT TIME$
     23:59:59
@@ -1,11 +1,13 @@
 /*!
-# `CLS`
+# `CLS [<mode>]`
 
 ## Purpose
 Clear the screen.
 
 ## Remarks
 Only if your terminal supports clearing the screen. It almost certainly does.
+The mode is 0 for the full screen, 1 for graphics, or 2 for the text window.
+The default is 0. A terminal treats every mode as a full clear.
 
 ## Example
 ```text
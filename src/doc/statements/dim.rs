@@ -5,10 +5,10 @@
 Prepare an array by defining its dimensions and size.
 
 ## Remarks
-Arrays are sparse. You can, for example, define a 32767 by 32767 array
-of 1073676300 elements. The number of values that can be stored is 64K
-for all variables combined. Index values are integers in the range of
-0-32767. Accessing an array before it is dimensioned automatically defines
+Arrays are sparse but may not be defined with more than 32767 elements.
+`DIM X(180,180)` is the largest square array. The number of values that can
+be stored is 64K for all variables combined. Index values are integers in
+the range of 0-32767. Accessing an array before it is dimensioned automatically defines
 it with a dimension of 10. The index is inclusive so `DIM X(10)`
 allows the use of `X(0)` to `X(10)`
 
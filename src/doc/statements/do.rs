@@ -0,0 +1,23 @@
+/*!
+# `DO [WHILE|UNTIL <expression>] : LOOP [WHILE|UNTIL <expression>]`
+
+## Purpose
+Loop while or until an expression is true.
+
+## Remarks
+A condition on `DO` is tested before each pass so the loop may not run at all.
+A condition on `LOOP` is tested after each pass so the loop runs at least once.
+Without any condition the loop runs until left with `GOTO` or `END`.
+`DO` and `LOOP` are matched up in the link phase like `WHILE` and `WEND`.
+
+## Example
+```text
+10 DO UNTIL I = 3
+20 I = I + 1
+30 PRINT I;
+40 LOOP
+RUN
+ 1  2  3
+```
+
+*/
@@ -1,5 +1,5 @@
 /*!
-# `END`
+# `END [<exit code>]`
 
 ## Purpose
 Immediately and unconditionally stop running the program
@@ -9,6 +9,8 @@ and return to a BASIC command prompt.
 Variables are left intact for inspection.
 `END` at the end of a program is optional.
 `CONT` may be used to resume execution.
+When a program file is run from the command line, the optional
+exit code becomes the exit status of the process.
 
 ## Example
 ```text
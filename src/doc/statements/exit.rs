@@ -0,0 +1,24 @@
+/*!
+# `EXIT FOR|WHILE`
+
+## Purpose
+Leave a `FOR` or `WHILE` loop early.
+
+## Remarks
+`EXIT FOR` removes the innermost `FOR` loop from the stack and continues
+after the `NEXT` that follows it in the source.
+`EXIT WHILE` continues after the `WEND` of the innermost `WHILE` loop.
+Both are matched up in the link phase according to their position in the source.
+
+## Example
+```text
+10 FOR I=1 TO 10
+20 IF I=4 THEN EXIT FOR
+30 PRINT I;
+40 NEXT I
+50 PRINT "DONE"
+RUN
+ 1  2  3 DONE
+```
+
+*/
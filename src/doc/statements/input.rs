@@ -1,5 +1,5 @@
 /*!
-# `INPUT [,]["<prompt string>";]<variable>[,<variable>...]`
+# `INPUT [;][,]["<prompt string>";]<variable>[,<variable>...]`
 
 ## Purpose
 Suspends execution and awaits a response from the terminal.
@@ -9,9 +9,13 @@ Suspends execution and awaits a response from the terminal.
 INPUT will capitalize ASCII lowercase by default. You can disable this feature
 with a comma immediately after the INPUT.
 
+A semicolon immediately after the INPUT keeps the cursor on the same
+line after the response is entered.
+
 ## Example
 ```text
 10 INPUT ,A$
+15 INPUT ;"CONTINUE"; C$
 20 INPUT "WHAT IS YOUR NAME AND AGE"; NAME$, AGE%
 ```
 
@@ -7,6 +7,7 @@ Load a BASIC program from the filesystem.
 ## Remarks
 The filename may contain paths and anything else your filesystem allows.
 The filename may also be a URL.
+A line in the file ending with ` _` continues onto the next line.
 
 ## Example 1
 ```text
@@ -9,6 +9,8 @@ A `PRINT` by itself outputs a newline (ASCII 10).
 To suppress the newline, use a semicolon (;) at the end.
 Separating expressions with nothing or a semicolon (;) will print them with nothing between.
 Output is divided into zones of 14 characters. A comma will advance to the start of next zone.
+`?` and `P.` may be typed as abbreviations for `PRINT`. Other statements
+have period abbreviations such as `G.` for `GOTO` and `L.` for `LIST`.
 
 ## Example
 ```text
@@ -6,6 +6,9 @@ Reads the information defined in `DATA` statements.
 
 ## Remarks
 An `?OUT OF DATA` error will occur when reading past the end.
+A number read into a string variable keeps its text from the `DATA` statement.
+A string read into a numeric variable is a `?SYNTAX ERROR`
+reported in the line of the `DATA` statement.
 
 ## Example
 ```text
@@ -0,0 +1,20 @@
+/*!
+# `WAIT <port>,<mask>[,<xor>]`
+
+## Purpose
+Pause until a hardware port matches a bit pattern.
+
+## Remarks
+There are no ports so every one reads as zero. `WAIT` is kept so old
+programs still run. It continues at once when `<xor> AND <mask>`
+is not zero, otherwise it waits until interrupted.
+The mask and xor values must be 0 to 255. The default xor is 0.
+`WAIT` is not found inside longer names so `WAITING` is a variable.
+
+## Example
+```text
+WAIT 1,0
+?BREAK
+```
+
+*/
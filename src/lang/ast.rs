@@ -4,9 +4,10 @@ use std::rc::Rc;
 #[derive(Debug, PartialEq)]
 pub enum Statement {
     Clear(Column),
-    Cls(Column),
+    Cls(Column, Expression),
     Cont(Column),
     Data(Column, Vec<Expression>),
+    Date(Column, Expression),
     Def(Column, Variable, Vec<Variable>, Expression),
     Defdbl(Column, Variable, Variable),
     Defint(Column, Variable, Variable),
@@ -14,8 +15,13 @@ pub enum Statement {
     Defstr(Column, Variable, Variable),
     Delete(Column, Expression, Expression),
     Dim(Column, Vec<Variable>),
-    End(Column),
+    Do(Column),
+    DoUntil(Column, Expression),
+    DoWhile(Column, Expression),
+    End(Column, Option<Expression>),
     Erase(Column, Vec<Variable>),
+    ExitFor(Column),
+    ExitWhile(Column),
     For(Column, Variable, Expression, Expression, Expression),
     Gosub(Column, Expression),
     Goto(Column, Expression),
@@ -24,6 +30,9 @@ pub enum Statement {
     Let(Column, Variable, Expression),
     List(Column, Expression, Expression),
     Load(Column, Expression),
+    Loop(Column),
+    LoopUntil(Column, Expression),
+    LoopWhile(Column, Expression),
     Mid(Column, Variable, Expression, Expression, Expression),
     New(Column),
     Next(Column, Vec<Variable>),
@@ -38,8 +47,10 @@ pub enum Statement {
     Save(Column, Expression),
     Stop(Column),
     Swap(Column, Variable, Variable),
+    Time(Column, Expression),
     Troff(Column),
     Tron(Column),
+    Wait(Column, Expression, Expression, Expression),
     Wend(Column),
     While(Column, Expression),
 }
@@ -117,8 +128,19 @@ impl AcceptVisitor for Statement {
     fn accept<V: Visitor>(&self, visitor: &mut V) {
         use Statement::*;
         match self {
-            Clear(_) | Cls(_) | Cont(_) | End(_) | New(_) | Stop(_) | Troff(_) | Tron(_)
-            | Return(_) | Wend(_) => {}
+            Clear(_)
+            | Cont(_)
+            | Do(_)
+            | End(_, None)
+            | ExitFor(_)
+            | ExitWhile(_)
+            | Loop(_)
+            | New(_)
+            | Stop(_)
+            | Troff(_)
+            | Tron(_)
+            | Return(_)
+            | Wend(_) => {}
             Data(_, vec_expr) | Print(_, vec_expr) => {
                 for v in vec_expr {
                     v.accept(visitor);
@@ -145,12 +167,20 @@ impl AcceptVisitor for Statement {
                 expr2.accept(visitor);
                 expr3.accept(visitor);
             }
-            Gosub(_, expr)
+            Cls(_, expr)
+            | Date(_, expr)
+            | DoUntil(_, expr)
+            | DoWhile(_, expr)
+            | End(_, Some(expr))
+            | Gosub(_, expr)
             | Goto(_, expr)
             | Load(_, expr)
+            | LoopUntil(_, expr)
+            | LoopWhile(_, expr)
             | Restore(_, expr)
             | Run(_, expr)
             | Save(_, expr)
+            | Time(_, expr)
             | While(_, expr) => {
                 expr.accept(visitor);
             }
@@ -184,7 +214,7 @@ impl AcceptVisitor for Statement {
                     expr.accept(visitor);
                 }
             }
-            Renum(_, expr1, expr2, expr3) => {
+            Renum(_, expr1, expr2, expr3) | Wait(_, expr1, expr2, expr3) => {
                 expr1.accept(visitor);
                 expr2.accept(visitor);
                 expr3.accept(visitor);
@@ -231,3 +261,183 @@ impl AcceptVisitor for Expression {
         visitor.visit_expression(self)
     }
 }
+
+/// ## S-expression dump
+///
+/// A compact form of parsed statements such as `(print (add 1 2) "\n")`.
+pub fn to_sexpr(statements: &[Statement]) -> String {
+    let vec_sexpr: Vec<String> = statements.iter().map(Statement::to_sexpr).collect();
+    vec_sexpr.join(" ")
+}
+
+fn sexpr_list(name: &str, items: Vec<String>) -> String {
+    if items.is_empty() {
+        format!("({})", name)
+    } else {
+        format!("({} {})", name, items.join(" "))
+    }
+}
+
+impl Statement {
+    fn to_sexpr(&self) -> String {
+        use Statement::*;
+        fn exprs(vec_expr: &[Expression]) -> Vec<String> {
+            vec_expr.iter().map(Expression::to_sexpr).collect()
+        }
+        fn vars(vec_var: &[Variable]) -> Vec<String> {
+            vec_var.iter().map(Variable::to_sexpr).collect()
+        }
+        let (name, items) = match self {
+            Clear(_) => ("clear", vec![]),
+            Cont(_) => ("cont", vec![]),
+            Do(_) => ("do", vec![]),
+            End(_, None) => ("end", vec![]),
+            ExitFor(_) => ("exit-for", vec![]),
+            ExitWhile(_) => ("exit-while", vec![]),
+            Loop(_) => ("loop", vec![]),
+            New(_) => ("new", vec![]),
+            Return(_) => ("return", vec![]),
+            Stop(_) => ("stop", vec![]),
+            Troff(_) => ("troff", vec![]),
+            Tron(_) => ("tron", vec![]),
+            Wend(_) => ("wend", vec![]),
+            Cls(_, expr) => ("cls", vec![expr.to_sexpr()]),
+            Date(_, expr) => ("date", vec![expr.to_sexpr()]),
+            DoUntil(_, expr) => ("do-until", vec![expr.to_sexpr()]),
+            DoWhile(_, expr) => ("do-while", vec![expr.to_sexpr()]),
+            End(_, Some(expr)) => ("end", vec![expr.to_sexpr()]),
+            Gosub(_, expr) => ("gosub", vec![expr.to_sexpr()]),
+            Goto(_, expr) => ("goto", vec![expr.to_sexpr()]),
+            Load(_, expr) => ("load", vec![expr.to_sexpr()]),
+            LoopUntil(_, expr) => ("loop-until", vec![expr.to_sexpr()]),
+            LoopWhile(_, expr) => ("loop-while", vec![expr.to_sexpr()]),
+            Restore(_, expr) => ("restore", vec![expr.to_sexpr()]),
+            Run(_, expr) => ("run", vec![expr.to_sexpr()]),
+            Save(_, expr) => ("save", vec![expr.to_sexpr()]),
+            Time(_, expr) => ("time", vec![expr.to_sexpr()]),
+            While(_, expr) => ("while", vec![expr.to_sexpr()]),
+            Data(_, vec_expr) => ("data", exprs(vec_expr)),
+            Print(_, vec_expr) => ("print", exprs(vec_expr)),
+            Dim(_, vec_var) => ("dim", vars(vec_var)),
+            Erase(_, vec_var) => ("erase", vars(vec_var)),
+            Next(_, vec_var) => ("next", vars(vec_var)),
+            Read(_, vec_var) => ("read", vars(vec_var)),
+            Def(_, var, vec_var, expr) => (
+                "def",
+                vec![
+                    var.to_sexpr(),
+                    sexpr_list("args", vars(vec_var)),
+                    expr.to_sexpr(),
+                ],
+            ),
+            Defdbl(_, var1, var2) => ("defdbl", vec![var1.to_sexpr(), var2.to_sexpr()]),
+            Defint(_, var1, var2) => ("defint", vec![var1.to_sexpr(), var2.to_sexpr()]),
+            Defsng(_, var1, var2) => ("defsng", vec![var1.to_sexpr(), var2.to_sexpr()]),
+            Defstr(_, var1, var2) => ("defstr", vec![var1.to_sexpr(), var2.to_sexpr()]),
+            Swap(_, var1, var2) => ("swap", vec![var1.to_sexpr(), var2.to_sexpr()]),
+            Delete(_, expr1, expr2) => ("delete", vec![expr1.to_sexpr(), expr2.to_sexpr()]),
+            List(_, expr1, expr2) => ("list", vec![expr1.to_sexpr(), expr2.to_sexpr()]),
+            Renum(_, expr1, expr2, expr3) => (
+                "renum",
+                vec![expr1.to_sexpr(), expr2.to_sexpr(), expr3.to_sexpr()],
+            ),
+            Wait(_, expr1, expr2, expr3) => (
+                "wait",
+                vec![expr1.to_sexpr(), expr2.to_sexpr(), expr3.to_sexpr()],
+            ),
+            For(_, var, expr1, expr2, expr3) | Mid(_, var, expr1, expr2, expr3) => (
+                if matches!(self, For(..)) {
+                    "for"
+                } else {
+                    "mid"
+                },
+                vec![
+                    var.to_sexpr(),
+                    expr1.to_sexpr(),
+                    expr2.to_sexpr(),
+                    expr3.to_sexpr(),
+                ],
+            ),
+            If(_, predicate, then_stmt, else_stmt) => (
+                "if",
+                vec![
+                    predicate.to_sexpr(),
+                    sexpr_list("then", then_stmt.iter().map(Statement::to_sexpr).collect()),
+                    sexpr_list("else", else_stmt.iter().map(Statement::to_sexpr).collect()),
+                ],
+            ),
+            Input(_, expr1, expr2, vec_var) => {
+                let mut items = vec![expr1.to_sexpr(), expr2.to_sexpr()];
+                items.append(&mut vars(vec_var));
+                ("input", items)
+            }
+            Let(_, var, expr) => ("let", vec![var.to_sexpr(), expr.to_sexpr()]),
+            OnGoto(_, expr, vec_expr) | OnGosub(_, expr, vec_expr) => {
+                let mut items = vec![expr.to_sexpr()];
+                items.append(&mut exprs(vec_expr));
+                let name = if matches!(self, OnGoto(..)) {
+                    "on-goto"
+                } else {
+                    "on-gosub"
+                };
+                (name, items)
+            }
+        };
+        sexpr_list(name, items)
+    }
+}
+
+impl Variable {
+    fn to_sexpr(&self) -> String {
+        match self {
+            Variable::Unary(_, ident) => ident.name().to_string(),
+            Variable::Array(_, ident, vec_expr) => sexpr_list(
+                ident.name(),
+                vec_expr.iter().map(Expression::to_sexpr).collect(),
+            ),
+        }
+    }
+}
+
+impl Ident {
+    fn name(&self) -> &str {
+        use Ident::*;
+        match self {
+            Plain(s) | String(s) | Single(s) | Double(s) | Integer(s) => s,
+        }
+    }
+}
+
+impl Expression {
+    fn to_sexpr(&self) -> String {
+        use Expression::*;
+        let (name, expr1, expr2) = match self {
+            Variable(var) => return var.to_sexpr(),
+            Single(_, n) => return n.to_string(),
+            Double(_, n) => return n.to_string(),
+            Integer(_, n) => return n.to_string(),
+            String(_, s) => return format!("{:?}", s),
+            Negation(_, expr) => return sexpr_list("neg", vec![expr.to_sexpr()]),
+            Not(_, expr) => return sexpr_list("not", vec![expr.to_sexpr()]),
+            Power(_, expr1, expr2) => ("pow", expr1, expr2),
+            Multiply(_, expr1, expr2) => ("mul", expr1, expr2),
+            Divide(_, expr1, expr2) => ("div", expr1, expr2),
+            DivideInt(_, expr1, expr2) => ("div-int", expr1, expr2),
+            Modulo(_, expr1, expr2) => ("mod", expr1, expr2),
+            Add(_, expr1, expr2) => ("add", expr1, expr2),
+            Subtract(_, expr1, expr2) => ("sub", expr1, expr2),
+            Equal(_, expr1, expr2) => ("eq", expr1, expr2),
+            NotEqual(_, expr1, expr2) => ("not-eq", expr1, expr2),
+            Less(_, expr1, expr2) => ("lt", expr1, expr2),
+            LessEqual(_, expr1, expr2) => ("lt-eq", expr1, expr2),
+            Greater(_, expr1, expr2) => ("gt", expr1, expr2),
+            GreaterEqual(_, expr1, expr2) => ("gt-eq", expr1, expr2),
+            And(_, expr1, expr2) => ("and", expr1, expr2),
+            Or(_, expr1, expr2) => ("or", expr1, expr2),
+            Xor(_, expr1, expr2) => ("xor", expr1, expr2),
+            Imp(_, expr1, expr2) => ("imp", expr1, expr2),
+            Eqv(_, expr1, expr2) => ("eqv", expr1, expr2),
+        };
+        sexpr_list(name, vec![expr1.to_sexpr(), expr2.to_sexpr()])
+    }
+}
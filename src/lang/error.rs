@@ -57,6 +57,16 @@ impl Error {
         }
     }
 
+    /// The classic error number, such as 2 for `?SYNTAX ERROR`.
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// Display with the error number in front: `Error 2: ?SYNTAX ERROR`
+    pub fn to_string_with_code(&self) -> String {
+        format!("Error {}: {}", self.code, self)
+    }
+
     pub fn is_direct(&self) -> bool {
         self.line_number.is_none()
     }
@@ -130,6 +140,8 @@ pub enum ErrorCode {
     ForWithoutNext = 26,
     WhileWithoutWend = 29,
     WendWithoutWhile = 30,
+    LoopWithoutDo = 31,
+    DoWithoutLoop = 32,
     InternalError = 51,
     FileNotFound = 53,
     FileAlreadyExists = 58,
@@ -173,6 +185,8 @@ impl std::fmt::Display for Error {
             26 => "FOR WITHOUT NEXT",
             29 => "WHILE WITHOUT WEND",
             30 => "WEND WITHOUT WHILE",
+            31 => "LOOP WITHOUT DO",
+            32 => "DO WITHOUT LOOP",
             50 => "FIELD OVERFLOW",
             51 => "INTERNAL ERROR",
             52 => "BAD FILE NUMBER",
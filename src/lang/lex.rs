@@ -99,6 +99,7 @@ impl BasicLexer {
         BasicLexer::trim_end(&mut tokens);
         BasicLexer::collapse_triples(&mut tokens);
         BasicLexer::collapse_doubles(&mut tokens);
+        BasicLexer::expand_abbreviations(&mut tokens);
         BasicLexer::separate_words(&mut tokens);
         (line_number, tokens)
     }
@@ -192,6 +193,57 @@ impl BasicLexer {
         }
     }
 
+    fn expand_abbreviations(tokens: &mut Vec<Token>) {
+        let mut locs: Vec<(usize, usize, Vec<Token>)> = vec![];
+        let mut statement_start = true;
+        for index in 0..tokens.len() {
+            if statement_start {
+                if let Some((len, replacement)) = BasicLexer::abbreviation(&tokens[index..]) {
+                    locs.push((index, len, replacement));
+                }
+            }
+            statement_start = match &tokens[index] {
+                Token::Whitespace(_) => statement_start,
+                Token::Colon | Token::Word(Word::Then) | Token::Word(Word::Else) => true,
+                _ => false,
+            };
+        }
+        while let Some((index, len, replacement)) = locs.pop() {
+            tokens.splice(index..index + len, replacement);
+        }
+    }
+
+    /// Expands an abbreviation such as `P.` at the start of the tokens and
+    /// returns how many tokens it replaces. A word inside the abbreviation
+    /// splits it, so `CON.` arrives as `C`, `ON`, and `.`.
+    fn abbreviation(tokens: &[Token]) -> Option<(usize, Vec<Token>)> {
+        let (s, num, len) = match tokens {
+            [Token::Ident(Ident::Plain(s)), Token::Literal(Literal::Single(num)), ..] => {
+                (s.to_string(), num, 2)
+            }
+            [Token::Ident(Ident::Plain(s)), Token::Word(word), Token::Literal(Literal::Single(num)), ..] => {
+                (format!("{}{}", s, word), num, 3)
+            }
+            _ => return None,
+        };
+        let token = Token::match_abbreviation(&s)?;
+        let rest = num.strip_prefix('.')?;
+        if rest.is_empty() {
+            Some((len, vec![token]))
+        } else if rest.chars().all(is_basic_digit) {
+            let mut rest: Vec<Token> = BasicLexer {
+                chars: rest.chars().collect(),
+                pending: VecDeque::default(),
+                remark: false,
+            }
+            .collect();
+            rest.insert(0, token);
+            Some((len, rest))
+        } else {
+            None
+        }
+    }
+
     fn separate_words(tokens: &mut Vec<Token>) {
         let mut locs: Vec<usize> = vec![];
         for (index, tt) in tokens.windows(2).enumerate() {
@@ -309,6 +361,18 @@ impl BasicLexer {
     }
 
     fn alphabetic(&mut self) -> Option<Token> {
+        if self.chars.len() >= 3
+            && self
+                .chars
+                .iter()
+                .take(3)
+                .map(|ch| ch.to_ascii_uppercase())
+                .eq("REM".chars())
+        {
+            // Keep the case of text joined to REM such as REMark.
+            self.chars.drain(..3);
+            return Some(Token::Word(Word::Rem1));
+        }
         let mut s = String::new();
         let mut digit = false;
         while let Some(ch) = self.chars.pop_front() {
@@ -357,28 +421,28 @@ impl BasicLexer {
 
     fn radix(&mut self) -> Option<Token> {
         self.chars.pop_front();
-        let is_hex = if matches!(self.chars.front(), Some('H') | Some('h')) {
-            self.chars.pop_front();
-            true
-        } else {
-            false
+        let radix = match self.chars.front() {
+            Some('H') | Some('h') => 16,
+            Some('B') | Some('b') => 2,
+            _ => 8,
         };
+        if radix != 8 {
+            self.chars.pop_front();
+        }
         let mut s = String::new();
         while let Some(ch) = self.chars.pop_front() {
             let ch = ch.to_ascii_uppercase();
-            if ('0'..='7').contains(&ch)
-                || (is_hex && (('8'..='9').contains(&ch) || ('A'..='F').contains(&ch)))
-            {
+            if ch.is_digit(radix) {
                 s.push(ch)
             } else {
                 self.chars.push_front(ch);
                 break;
             }
         }
-        if is_hex {
-            Some(Token::Literal(Literal::Hex(s)))
-        } else {
-            Some(Token::Literal(Literal::Octal(s)))
+        match radix {
+            16 => Some(Token::Literal(Literal::Hex(s))),
+            2 => Some(Token::Literal(Literal::Binary(s))),
+            _ => Some(Token::Literal(Literal::Octal(s))),
         }
     }
 
@@ -1,7 +1,7 @@
 use super::{ast::*, lex, parse, token, Column, Error, LineNumber, MaxValue};
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Line {
     number: LineNumber,
     tokens: Vec<token::Token>,
@@ -13,6 +13,12 @@ impl Line {
         Line { number, tokens }
     }
 
+    /// Build a line from tokens without lexing. Whitespace is not
+    /// inserted so include `Token::Whitespace` where it is wanted.
+    pub fn from_tokens(number: LineNumber, tokens: Vec<token::Token>) -> Line {
+        Line { number, tokens }
+    }
+
     pub fn number(&self) -> LineNumber {
         self.number
     }
@@ -25,6 +31,54 @@ impl Line {
         self.tokens.is_empty()
     }
 
+    /// Tokens paired with their character range in the listed line,
+    /// which includes the line number. Used for syntax highlighting.
+    pub fn tokens_with_spans(&self) -> Vec<(token::Token, Column)> {
+        let mut start = match self.number {
+            Some(number) => number.to_string().len() + 1,
+            None => 0,
+        };
+        self.tokens
+            .iter()
+            .map(|token| {
+                let end = start + token.to_string().chars().count();
+                let span = start..end;
+                start = end;
+                (token.clone(), span)
+            })
+            .collect()
+    }
+
+    /// Text of the tokens within a column of the AST, such as one `DATA` item.
+    pub fn text_at(&self, col: &Column) -> String {
+        let mut start = 0;
+        let mut text = String::new();
+        for token in &self.tokens {
+            let token = token.to_string();
+            let end = start + token.chars().count();
+            if start >= col.start && end <= col.end {
+                text.push_str(&token);
+            }
+            start = end;
+        }
+        text
+    }
+
+    /// Text the lexer could not recognize, such as `@`, with its character
+    /// range in the listed line. Remarks are not checked.
+    pub fn lex_errors(&self) -> Vec<(Column, String)> {
+        let mut remark = false;
+        let mut errors = vec![];
+        for (token, span) in self.tokens_with_spans() {
+            match token {
+                token::Token::Word(token::Word::Rem1 | token::Word::Rem2) => remark = true,
+                token::Token::Unknown(s) if !remark => errors.push((span, s)),
+                _ => {}
+            }
+        }
+        errors
+    }
+
     pub fn ast(&self) -> Result<Vec<Statement>, Error> {
         parse(self.number, &self.tokens)
     }
@@ -70,7 +124,7 @@ struct RenumVisitor<'a> {
 }
 
 impl<'a> RenumVisitor<'a> {
-    fn new(changes: &HashMap<u16, u16>) -> RenumVisitor {
+    fn new(changes: &HashMap<u16, u16>) -> RenumVisitor<'_> {
         RenumVisitor {
             changes,
             replace: vec![],
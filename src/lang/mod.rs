@@ -38,6 +38,7 @@ pub use error::ErrorCode;
 pub use lex::lex;
 pub use line::Line;
 pub use parse::parse;
+pub use parse::parse_all;
 
 pub mod ast;
 pub mod token;
@@ -16,6 +16,17 @@ pub fn parse(line_number: LineNumber, tokens: &[Token]) -> Result<Vec<Statement>
     }
 }
 
+/// Parse every statement, skipping to the next colon after an error,
+/// so that all errors in the line are reported.
+pub fn parse_all(line_number: LineNumber, tokens: &[Token]) -> (Vec<Statement>, Vec<Error>) {
+    let (statements, mut errors) = BasicParser::parse_all(tokens);
+    let errors = errors
+        .drain(..)
+        .map(|e| e.in_line_number(line_number))
+        .collect();
+    (statements, errors)
+}
+
 struct BasicParser<'a> {
     token_stream: std::slice::Iter<'a, Token>,
     peeked: Option<&'a Token>,
@@ -24,22 +35,76 @@ struct BasicParser<'a> {
 }
 
 impl<'a> BasicParser<'a> {
-    fn parse(tokens: &'a [Token]) -> Result<Vec<Statement>> {
-        let mut parse = BasicParser {
+    fn new(tokens: &'a [Token]) -> BasicParser<'a> {
+        BasicParser {
             token_stream: tokens.iter(),
             peeked: None,
             rem: false,
             col: 0..0,
-        };
-        match parse.peek() {
+        }
+    }
+
+    fn parse(tokens: &'a [Token]) -> Result<Vec<Statement>> {
+        let mut parse = BasicParser::new(tokens);
+        parse.expect_no_line_number()?;
+        parse.expect_statements()
+    }
+
+    fn parse_all(tokens: &'a [Token]) -> (Vec<Statement>, Vec<Error>) {
+        let mut parse = BasicParser::new(tokens);
+        let mut statements: Vec<Statement> = vec![];
+        let mut errors: Vec<Error> = vec![];
+        if let Err(error) = parse.expect_no_line_number() {
+            errors.push(error);
+            return (statements, errors);
+        }
+        let mut expect_colon = false;
+        loop {
+            match parse.peek() {
+                None | Some(Token::Word(Word::Else)) => return (statements, errors),
+                Some(Token::Colon) => {
+                    expect_colon = false;
+                    parse.next();
+                    continue;
+                }
+                Some(_) => {
+                    let result = if expect_colon {
+                        Err(error!(SyntaxError, ..&parse.col; "UNEXPECTED TOKEN"))
+                    } else {
+                        Statement::expect(&mut parse)
+                    };
+                    match result {
+                        Ok(statement) => {
+                            statements.push(statement);
+                            expect_colon = true;
+                        }
+                        Err(error) => {
+                            errors.push(error);
+                            let consumed = tokens.len() - parse.token_stream.as_slice().len();
+                            let past_colon = parse.peeked.is_none()
+                                && consumed > 0
+                                && tokens[consumed - 1] == Token::Colon;
+                            while !past_colon && !matches!(parse.peek(), None | Some(Token::Colon))
+                            {
+                                parse.next();
+                            }
+                            expect_colon = false;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn expect_no_line_number(&mut self) -> Result<()> {
+        match self.peek() {
             Some(Token::Literal(Literal::Integer(_)))
             | Some(Token::Literal(Literal::Single(_)))
             | Some(Token::Literal(Literal::Double(_))) => {
-                return Err(error!(UndefinedLine, ..&parse.col; "INVALID LINE NUMBER"));
+                Err(error!(UndefinedLine, ..&self.col; "INVALID LINE NUMBER"))
             }
-            _ => {}
+            _ => Ok(()),
         }
-        parse.expect_statements()
     }
 
     fn next(&mut self) -> Option<&'a Token> {
@@ -508,17 +573,21 @@ impl Expression {
             }
         }
         fn parse_radix(col: Column, src: &str, radix: u32) -> Result<Expression> {
-            match i16::from_str_radix(src, radix) {
-                Ok(num) => Ok(Expression::Integer(col, num)),
+            match u16::from_str_radix(src, radix) {
+                Ok(num) => Ok(Expression::Integer(col, num as i16)),
                 Err(_) => Err(error!(Overflow, ..&col)),
             }
         }
         match lit {
             Literal::Hex(s) => parse_radix(col, s, 16),
             Literal::Octal(s) => parse_radix(col, s, 8),
+            Literal::Binary(s) => parse_radix(col, s, 2),
             Literal::Single(s) => Ok(Expression::Single(col.clone(), parse(col, s)?)),
             Literal::Double(s) => Ok(Expression::Double(col.clone(), parse(col, s)?)),
-            Literal::Integer(s) => Ok(Expression::Integer(col.clone(), parse(col, s)?)),
+            Literal::Integer(s) => match parse(col.clone(), s) {
+                Ok(num) => Ok(Expression::Integer(col, num)),
+                Err(_) => Err(error!(Overflow, ..&col)),
+            },
             Literal::String(s) => {
                 if s.chars().count() > 255 {
                     Err(error!(StringTooLong, ..&col; "MAXIMUM LITERAL LENGTH IS 255"))
@@ -549,8 +618,10 @@ impl Statement {
                     Defstr => return Self::r#defstr(parse),
                     Delete => return Self::r#delete(parse),
                     Dim => return Self::r#dim(parse),
+                    Do => return Self::r#do(parse),
                     End => return Self::r#end(parse),
                     Erase => return Self::r#erase(parse),
+                    Exit => return Self::r#exit(parse),
                     For => return Self::r#for(parse),
                     Gosub => return Self::r#gosub(parse),
                     Goto => return Self::r#goto(parse),
@@ -559,6 +630,7 @@ impl Statement {
                     Let => return Self::r#let(parse, false),
                     List => return Self::r#list(parse),
                     Load => return Self::r#load(parse),
+                    Loop => return Self::r#loop(parse),
                     New => return Self::r#new(parse),
                     Next => return Self::r#next(parse),
                     On => return Self::r#on(parse),
@@ -573,9 +645,10 @@ impl Statement {
                     Swap => return Self::r#swap(parse),
                     Troff => return Self::r#troff(parse),
                     Tron => return Self::r#tron(parse),
+                    Wait => return Self::r#wait(parse),
                     Wend => return Self::r#wend(parse),
                     While => return Self::r#while(parse),
-                    Else | Rem1 | Rem2 | Step | Then | To => {}
+                    Else | Rem1 | Rem2 | Step | Then | To | Until => {}
                 }
             }
             _ => {}
@@ -595,7 +668,14 @@ impl Statement {
     }
 
     fn r#cls(parse: &mut BasicParser) -> Result<Statement> {
-        Ok(Statement::Cls(parse.col.clone()))
+        let column = parse.col.clone();
+        match parse.peek() {
+            None | Some(Token::Colon) | Some(Token::Word(Word::Else)) => Ok(Statement::Cls(
+                column.clone(),
+                Expression::Integer(column, 0),
+            )),
+            _ => Ok(Statement::Cls(column, parse.expect_expression()?)),
+        }
     }
 
     fn r#cont(parse: &mut BasicParser) -> Result<Statement> {
@@ -671,8 +751,36 @@ impl Statement {
         Ok(Statement::Dim(column, var_list))
     }
 
+    fn r#do(parse: &mut BasicParser) -> Result<Statement> {
+        let column = parse.col.clone();
+        if parse.maybe(Token::Word(Word::While)) {
+            let column = column.start..parse.col.end;
+            return Ok(Statement::DoWhile(column, parse.expect_expression()?));
+        }
+        if parse.maybe(Token::Word(Word::Until)) {
+            let column = column.start..parse.col.end;
+            return Ok(Statement::DoUntil(column, parse.expect_expression()?));
+        }
+        Ok(Statement::Do(column))
+    }
+
     fn r#end(parse: &mut BasicParser) -> Result<Statement> {
-        Ok(Statement::End(parse.col.clone()))
+        let column = parse.col.clone();
+        match parse.peek() {
+            None | Some(Token::Colon) | Some(Token::Word(Word::Else)) => {
+                Ok(Statement::End(column, None))
+            }
+            _ => Ok(Statement::End(column, Some(parse.expect_expression()?))),
+        }
+    }
+
+    fn r#exit(parse: &mut BasicParser) -> Result<Statement> {
+        let column = parse.col.clone();
+        match parse.next() {
+            Some(Token::Word(Word::For)) => Ok(Statement::ExitFor(column.start..parse.col.end)),
+            Some(Token::Word(Word::While)) => Ok(Statement::ExitWhile(column.start..parse.col.end)),
+            _ => Err(error!(SyntaxError, ..&parse.col; "EXPECTED FOR OR WHILE")),
+        }
     }
 
     fn r#erase(parse: &mut BasicParser) -> Result<Statement> {
@@ -753,12 +861,17 @@ impl Statement {
     fn r#input(parse: &mut BasicParser) -> Result<Statement> {
         let column = parse.col.clone();
         let mut prompt_col = column.end..column.end;
-        let caps = if let Some(Token::Comma) = parse.peek() {
+        // Flags: 1 for caps lock, 2 for no newline after entry.
+        let mut flags = 1;
+        if let Some(Token::Semicolon) = parse.peek() {
             parse.next();
-            Expression::Integer(parse.col.clone(), 0)
-        } else {
-            Expression::Integer(parse.col.start..parse.col.start, -1)
-        };
+            flags |= 2;
+        }
+        if let Some(Token::Comma) = parse.peek() {
+            parse.next();
+            flags &= !1;
+        }
+        let caps = Expression::Integer(parse.col.clone(), flags);
         let prompt = match parse.peek() {
             Some(Token::Literal(Literal::String(s))) => {
                 parse.next();
@@ -797,13 +910,23 @@ impl Statement {
                 let len = if parse.maybe(Token::Comma) {
                     parse.expect_expression()?
                 } else {
-                    Expression::Integer(parse.col.start..parse.col.start, i16::max_value())
+                    Expression::Integer(parse.col.start..parse.col.start, i16::MAX)
                 };
                 parse.expect(Token::RParen)?;
                 parse.expect(Token::Operator(Operator::Equal))?;
                 let expr = parse.expect_expression()?;
                 return Ok(Statement::Mid(column, var, pos, len, expr));
             }
+            if s == "DATE$" || s == "TIME$" {
+                let is_date = s == "DATE$";
+                parse.next();
+                parse.expect(Token::Operator(Operator::Equal))?;
+                let expr = parse.expect_expression()?;
+                if is_date {
+                    return Ok(Statement::Date(column, expr));
+                }
+                return Ok(Statement::Time(column, expr));
+            }
         }
         let var = parse.expect_var()?;
         match parse.next() {
@@ -833,6 +956,19 @@ impl Statement {
         ))
     }
 
+    fn r#loop(parse: &mut BasicParser) -> Result<Statement> {
+        let column = parse.col.clone();
+        if parse.maybe(Token::Word(Word::While)) {
+            let column = column.start..parse.col.end;
+            return Ok(Statement::LoopWhile(column, parse.expect_expression()?));
+        }
+        if parse.maybe(Token::Word(Word::Until)) {
+            let column = column.start..parse.col.end;
+            return Ok(Statement::LoopUntil(column, parse.expect_expression()?));
+        }
+        Ok(Statement::Loop(column))
+    }
+
     fn r#new(parse: &mut BasicParser) -> Result<Statement> {
         Ok(Statement::New(parse.col.clone()))
     }
@@ -981,6 +1117,19 @@ impl Statement {
         Ok(Statement::Tron(parse.col.clone()))
     }
 
+    fn r#wait(parse: &mut BasicParser) -> Result<Statement> {
+        let column = parse.col.clone();
+        let port = parse.expect_expression()?;
+        parse.expect(Token::Comma)?;
+        let mask = parse.expect_expression()?;
+        let xor = if parse.maybe(Token::Comma) {
+            parse.expect_expression()?
+        } else {
+            Expression::Integer(parse.col.end..parse.col.end, 0)
+        };
+        Ok(Statement::Wait(column, port, mask, xor))
+    }
+
     fn r#wend(parse: &mut BasicParser) -> Result<Statement> {
         Ok(Statement::Wend(parse.col.clone()))
     }
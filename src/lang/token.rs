@@ -72,23 +72,41 @@ impl Token {
             ("TO", Token::Word(Word::To)),
         ]
         .iter()
-        .filter_map(|(ts, tk)| {
-            s.find(ts).map(|idx| (idx, ts.len(), tk.clone()))
-        })
+        .filter_map(|(ts, tk)| s.find(ts).map(|idx| (idx, ts.len(), tk.clone())))
         .min_by_key(|(i, _, _)| *i)
         {
             if idx == 0 {
                 v.push_back(token);
                 s = &s[len..];
             } else {
-                v.push_back(Token::Ident(Ident::Plain(s[..idx].into())));
+                v.push_back(match Token::match_whole_word(&s[..idx]) {
+                    Some(word) => word,
+                    None => Token::Ident(Ident::Plain(s[..idx].into())),
+                });
                 v.push_back(token);
                 s = &s[(idx + len)..];
             }
         }
+        if let Some(word) = Token::match_whole_word(s) {
+            v.push_back(word);
+            return String::new();
+        }
         s.to_string()
     }
 
+    /// Words that ROM BASIC did not have are only found when not joined
+    /// to other letters so names like `DOT` and `LOOPS` still work.
+    fn match_whole_word(s: &str) -> Option<Token> {
+        match s {
+            "DO" => Some(Token::Word(Word::Do)),
+            "EXIT" => Some(Token::Word(Word::Exit)),
+            "LOOP" => Some(Token::Word(Word::Loop)),
+            "UNTIL" => Some(Token::Word(Word::Until)),
+            "WAIT" => Some(Token::Word(Word::Wait)),
+            _ => None,
+        }
+    }
+
     pub fn match_minutia(s: &str) -> Option<Token> {
         match s {
             "(" => Some(Token::LParen),
@@ -111,6 +129,30 @@ impl Token {
         }
     }
 
+    pub fn match_abbreviation(s: &str) -> Option<Token> {
+        match s {
+            "CON" => Some(Token::Word(Word::Cont)),
+            "D" => Some(Token::Word(Word::Data)),
+            "DI" => Some(Token::Word(Word::Dim)),
+            "E" => Some(Token::Word(Word::End)),
+            "F" => Some(Token::Word(Word::For)),
+            "G" => Some(Token::Word(Word::Goto)),
+            "GOS" => Some(Token::Word(Word::Gosub)),
+            "I" => Some(Token::Word(Word::Input)),
+            "L" => Some(Token::Word(Word::List)),
+            "LE" => Some(Token::Word(Word::Let)),
+            "LO" => Some(Token::Word(Word::Load)),
+            "N" => Some(Token::Word(Word::Next)),
+            "P" | "PR" => Some(Token::Word(Word::Print)),
+            "REA" => Some(Token::Word(Word::Read)),
+            "RES" => Some(Token::Word(Word::Restore)),
+            "RET" => Some(Token::Word(Word::Return)),
+            "RU" => Some(Token::Word(Word::Run)),
+            "S" => Some(Token::Word(Word::Save)),
+            _ => None,
+        }
+    }
+
     pub fn is_word(&self) -> bool {
         match self {
             Token::Word(_) => true,
@@ -150,7 +192,7 @@ impl TryFrom<&Token> for LineNumber {
                 Literal::Integer(s) => s,
                 Literal::Single(s) => s,
                 Literal::Double(s) => s,
-                Literal::Hex(_) | Literal::Octal(_) | Literal::String(_) => "",
+                Literal::Hex(_) | Literal::Octal(_) | Literal::Binary(_) | Literal::String(_) => "",
             };
             if s.chars().all(|c| c.is_ascii_digit()) {
                 if let Ok(line) = s.parse::<u16>() {
@@ -172,6 +214,7 @@ pub enum Literal {
     Integer(String),
     Hex(String),
     Octal(String),
+    Binary(String),
     String(String),
 }
 
@@ -184,6 +227,7 @@ impl std::fmt::Display for Literal {
             Integer(s) => write!(f, "{}", s),
             Hex(s) => write!(f, "&H{}", s),
             Octal(s) => write!(f, "&{}", s),
+            Binary(s) => write!(f, "&B{}", s),
             String(s) => write!(f, "\"{}\"", s),
         }
     }
@@ -202,9 +246,11 @@ pub enum Word {
     Defstr,
     Delete,
     Dim,
+    Do,
     Else,
     End,
     Erase,
+    Exit,
     For,
     Gosub,
     Goto,
@@ -213,6 +259,7 @@ pub enum Word {
     Let,
     List,
     Load,
+    Loop,
     New,
     Next,
     On,
@@ -232,6 +279,8 @@ pub enum Word {
     To,
     Troff,
     Tron,
+    Until,
+    Wait,
     Wend,
     While,
 }
@@ -251,9 +300,11 @@ impl std::fmt::Display for Word {
             Defstr => write!(f, "DEFSTR"),
             Delete => write!(f, "DELETE"),
             Dim => write!(f, "DIM"),
+            Do => write!(f, "DO"),
             Else => write!(f, "ELSE"),
             End => write!(f, "END"),
             Erase => write!(f, "ERASE"),
+            Exit => write!(f, "EXIT"),
             For => write!(f, "FOR"),
             Gosub => write!(f, "GOSUB"),
             Goto => write!(f, "GOTO"),
@@ -262,6 +313,7 @@ impl std::fmt::Display for Word {
             Let => write!(f, "LET"),
             List => write!(f, "LIST"),
             Load => write!(f, "LOAD"),
+            Loop => write!(f, "LOOP"),
             New => write!(f, "NEW"),
             Next => write!(f, "NEXT"),
             On => write!(f, "ON"),
@@ -281,6 +333,8 @@ impl std::fmt::Display for Word {
             To => write!(f, "TO"),
             Troff => write!(f, "TROFF"),
             Tron => write!(f, "TRON"),
+            Until => write!(f, "UNTIL"),
+            Wait => write!(f, "WAIT"),
             Wend => write!(f, "WEND"),
             While => write!(f, "WHILE"),
         }
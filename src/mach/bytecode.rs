@@ -0,0 +1,370 @@
+use super::{Address, Opcode, Val};
+use crate::error;
+use crate::lang::{Column, Error, LineNumber};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+type Result<T> = std::result::Result<T, Error>;
+
+const MAGIC: &[u8] = b"BASB\x02";
+
+/// Opcodes without data are encoded by their index in this table.
+/// Append only; the position of each entry is part of the format.
+const UNIT_OPCODES: &[Opcode] = &[
+    Opcode::On,
+    Opcode::Return,
+    Opcode::Clear,
+    Opcode::Cls,
+    Opcode::Cont,
+    Opcode::Defdbl,
+    Opcode::Defint,
+    Opcode::Defsng,
+    Opcode::Defstr,
+    Opcode::Delete,
+    Opcode::End,
+    Opcode::Exit,
+    Opcode::LetDate,
+    Opcode::LetMid,
+    Opcode::LetTime,
+    Opcode::List,
+    Opcode::Load,
+    Opcode::LoadRun,
+    Opcode::New,
+    Opcode::Print,
+    Opcode::Renum,
+    Opcode::Save,
+    Opcode::Stop,
+    Opcode::Swap,
+    Opcode::Troff,
+    Opcode::Tron,
+    Opcode::Neg,
+    Opcode::Pow,
+    Opcode::Mul,
+    Opcode::Div,
+    Opcode::DivInt,
+    Opcode::Mod,
+    Opcode::Add,
+    Opcode::Sub,
+    Opcode::Eq,
+    Opcode::NotEq,
+    Opcode::Lt,
+    Opcode::LtEq,
+    Opcode::Gt,
+    Opcode::GtEq,
+    Opcode::Not,
+    Opcode::And,
+    Opcode::Or,
+    Opcode::Xor,
+    Opcode::Imp,
+    Opcode::Eqv,
+    Opcode::Abs,
+    Opcode::Asc,
+    Opcode::Atn,
+    Opcode::Cdbl,
+    Opcode::Chr,
+    Opcode::Cint,
+    Opcode::Cos,
+    Opcode::Csng,
+    Opcode::Date,
+    Opcode::Exp,
+    Opcode::Fix,
+    Opcode::Hex,
+    Opcode::Inkey,
+    Opcode::Instr,
+    Opcode::Int,
+    Opcode::Left,
+    Opcode::Len,
+    Opcode::Log,
+    Opcode::Mid,
+    Opcode::Oct,
+    Opcode::Pos,
+    Opcode::Right,
+    Opcode::Rnd,
+    Opcode::Sgn,
+    Opcode::Sin,
+    Opcode::Spc,
+    Opcode::Sqr,
+    Opcode::Str,
+    Opcode::String,
+    Opcode::Tab,
+    Opcode::Tan,
+    Opcode::Time,
+    Opcode::Val,
+    Opcode::Wait,
+];
+
+/// Tags below this are opcodes with data.
+const UNIT_TAG: u8 = 32;
+
+/// ## Bytecode writer
+///
+/// Strings are stored once in a table written ahead of the body.
+
+#[derive(Debug, Default)]
+pub struct Writer {
+    body: Vec<u8>,
+    strings: Vec<Rc<str>>,
+    string_index: HashMap<Rc<str>, u32>,
+}
+
+impl Writer {
+    pub fn finish(self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        let mut table = Writer::default();
+        table.usize(self.strings.len());
+        for s in &self.strings {
+            table.usize(s.len());
+            table.body.extend_from_slice(s.as_bytes());
+        }
+        bytes.append(&mut table.body);
+        bytes.extend(self.body);
+        bytes
+    }
+
+    pub fn u8(&mut self, n: u8) {
+        self.body.push(n);
+    }
+
+    pub fn usize(&mut self, n: usize) {
+        self.body.extend_from_slice(&(n as u32).to_le_bytes());
+    }
+
+    pub fn isize(&mut self, n: isize) {
+        self.body.extend_from_slice(&(n as i64).to_le_bytes());
+    }
+
+    pub fn bool(&mut self, b: bool) {
+        self.u8(b as u8);
+    }
+
+    pub fn line_number(&mut self, line_number: LineNumber) {
+        match line_number {
+            Some(num) => {
+                self.u8(1);
+                self.body.extend_from_slice(&num.to_le_bytes());
+            }
+            None => self.u8(0),
+        }
+    }
+
+    pub fn column(&mut self, column: &Column) {
+        self.usize(column.start);
+        self.usize(column.end);
+    }
+
+    pub fn string(&mut self, s: &Rc<str>) {
+        let index = match self.string_index.get(s) {
+            Some(index) => *index,
+            None => {
+                let index = self.strings.len() as u32;
+                self.strings.push(s.clone());
+                self.string_index.insert(s.clone(), index);
+                index
+            }
+        };
+        self.body.extend_from_slice(&index.to_le_bytes());
+    }
+
+    pub fn val(&mut self, val: &Val) {
+        match val {
+            Val::String(s) => {
+                self.u8(0);
+                self.string(s);
+            }
+            Val::Single(n) => {
+                self.u8(1);
+                self.body.extend_from_slice(&n.to_le_bytes());
+            }
+            Val::Double(n) => {
+                self.u8(2);
+                self.body.extend_from_slice(&n.to_le_bytes());
+            }
+            Val::Integer(n) => {
+                self.u8(3);
+                self.body.extend_from_slice(&n.to_le_bytes());
+            }
+            Val::Return(addr) => {
+                self.u8(4);
+                self.usize(*addr);
+            }
+            Val::Next(addr) => {
+                self.u8(5);
+                self.usize(*addr);
+            }
+        }
+    }
+
+    pub fn opcode(&mut self, op: &Opcode) {
+        use Opcode::*;
+        match op {
+            Literal(val) => {
+                self.u8(0);
+                self.val(val);
+            }
+            Push(s) => self.tagged_string(1, s),
+            Pop(s) => self.tagged_string(2, s),
+            PushArr(s) => self.tagged_string(3, s),
+            PopArr(s) => self.tagged_string(4, s),
+            DimArr(s) => self.tagged_string(5, s),
+            EraseArr(s) => self.tagged_string(6, s),
+            Next(s) => self.tagged_string(7, s),
+            Def(s) => self.tagged_string(8, s),
+            Fn(s) => self.tagged_string(9, s),
+            Input(s) => self.tagged_string(10, s),
+            Read(s) => self.tagged_string(11, s),
+            IfNot(addr) => self.tagged_usize(12, *addr),
+            Jump(addr) => self.tagged_usize(13, *addr),
+            ExitFor(addr) => self.tagged_usize(14, *addr),
+            Restore(addr) => self.tagged_usize(15, *addr),
+            Extern(s) => self.tagged_string(16, s),
+            On | Return | Clear | Cls | Cont | Defdbl | Defint | Defsng | Defstr | Delete | End
+            | Exit | LetDate | LetMid | LetTime | List | Load | LoadRun | New | Print | Renum
+            | Save | Stop | Swap | Troff | Tron | Wait | Neg | Pow | Mul | Div | DivInt | Mod
+            | Add | Sub | Eq | NotEq | Lt | LtEq | Gt | GtEq | Not | And | Or | Xor | Imp | Eqv
+            | Abs | Asc | Atn | Cdbl | Chr | Cint | Cos | Csng | Date | Exp | Fix | Hex | Inkey
+            | Instr | Int | Left | Len | Log | Mid | Oct | Pos | Right | Rnd | Sgn | Sin | Spc
+            | Sqr | Str | String | Tab | Tan | Time | Val => {
+                let discriminant = std::mem::discriminant(op);
+                let index = UNIT_OPCODES
+                    .iter()
+                    .position(|unit| std::mem::discriminant(unit) == discriminant)
+                    .expect("Opcode missing from bytecode table.");
+                self.u8(UNIT_TAG + index as u8);
+            }
+        }
+    }
+
+    fn tagged_string(&mut self, tag: u8, s: &Rc<str>) {
+        self.u8(tag);
+        self.string(s);
+    }
+
+    fn tagged_usize(&mut self, tag: u8, n: usize) {
+        self.u8(tag);
+        self.usize(n);
+    }
+}
+
+/// ## Bytecode reader
+
+#[derive(Debug)]
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    strings: Vec<Rc<str>>,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Reader<'a>> {
+        let bytes = match bytes.strip_prefix(MAGIC) {
+            Some(bytes) => bytes,
+            None => return Err(Reader::invalid()),
+        };
+        let mut reader = Reader {
+            bytes,
+            strings: vec![],
+        };
+        for _ in 0..reader.usize()? {
+            let len = reader.usize()?;
+            let s = std::str::from_utf8(reader.take(len)?).map_err(|_| Reader::invalid())?;
+            reader.strings.push(s.into());
+        }
+        Ok(reader)
+    }
+
+    fn invalid() -> Error {
+        error!(SyntaxError; "INVALID BYTECODE")
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.bytes.len() {
+            return Err(Reader::invalid());
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        <[u8; N]>::try_from(self.take(N)?).map_err(|_| Reader::invalid())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn usize(&mut self) -> Result<usize> {
+        Ok(u32::from_le_bytes(self.array()?) as usize)
+    }
+
+    pub fn isize(&mut self) -> Result<isize> {
+        Ok(i64::from_le_bytes(self.array()?) as isize)
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn line_number(&mut self) -> Result<LineNumber> {
+        match self.u8()? {
+            0 => Ok(None),
+            _ => Ok(Some(u16::from_le_bytes(self.array()?))),
+        }
+    }
+
+    pub fn column(&mut self) -> Result<Column> {
+        Ok(self.usize()?..self.usize()?)
+    }
+
+    pub fn string(&mut self) -> Result<Rc<str>> {
+        let index = u32::from_le_bytes(self.array()?) as usize;
+        match self.strings.get(index) {
+            Some(s) => Ok(s.clone()),
+            None => Err(Reader::invalid()),
+        }
+    }
+
+    pub fn val(&mut self) -> Result<Val> {
+        Ok(match self.u8()? {
+            0 => Val::String(self.string()?),
+            1 => Val::Single(f32::from_le_bytes(self.array()?)),
+            2 => Val::Double(f64::from_le_bytes(self.array()?)),
+            3 => Val::Integer(i16::from_le_bytes(self.array()?)),
+            4 => Val::Return(self.usize()? as Address),
+            5 => Val::Next(self.usize()? as Address),
+            _ => return Err(Reader::invalid()),
+        })
+    }
+
+    pub fn opcode(&mut self) -> Result<Opcode> {
+        use Opcode::*;
+        Ok(match self.u8()? {
+            0 => Literal(self.val()?),
+            1 => Push(self.string()?),
+            2 => Pop(self.string()?),
+            3 => PushArr(self.string()?),
+            4 => PopArr(self.string()?),
+            5 => DimArr(self.string()?),
+            6 => EraseArr(self.string()?),
+            7 => Next(self.string()?),
+            8 => Def(self.string()?),
+            9 => Fn(self.string()?),
+            10 => Input(self.string()?),
+            11 => Read(self.string()?),
+            12 => IfNot(self.usize()?),
+            13 => Jump(self.usize()?),
+            14 => ExitFor(self.usize()?),
+            15 => Restore(self.usize()?),
+            16 => Extern(self.string()?),
+            tag if tag >= UNIT_TAG => match UNIT_OPCODES.get((tag - UNIT_TAG) as usize) {
+                Some(op) => op.clone(),
+                None => return Err(Reader::invalid()),
+            },
+            _ => return Err(Reader::invalid()),
+        })
+    }
+}
@@ -0,0 +1,20 @@
+extern crate chrono;
+use chrono::NaiveDateTime;
+
+/// ## Clock for `TIME$` and `DATE$`
+///
+/// The runtime reads local time from the system unless the host
+/// provides a clock, which allows simulated or fixed time.
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// Local time from the operating system.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
@@ -1,25 +1,28 @@
 use super::{Function, Link, Opcode, Program, Stack, Val};
 use crate::error;
 use crate::lang::ast::{self, AcceptVisitor};
-use crate::lang::{Column, Error, LineNumber};
+use crate::lang::{Column, Error, Line, LineNumber};
 use std::convert::TryFrom;
+use std::ops::RangeInclusive;
 use std::rc::Rc;
 
 type Result<T> = std::result::Result<T, Error>;
 
-pub fn codegen(program: &mut Program, ast: &[ast::Statement]) {
-    Visitor::accept(program, ast)
+pub fn codegen(program: &mut Program, ast: &[ast::Statement], line: &Line) {
+    Visitor::accept(program, ast, line)
 }
 
 struct Visitor<'a> {
     link: &'a mut Program,
+    line: &'a Line,
     gen: Generator,
 }
 
 impl<'a> Visitor<'a> {
-    fn accept(program: &mut Program, ast: &[ast::Statement]) {
+    fn accept(program: &mut Program, ast: &[ast::Statement], line: &Line) {
         let mut this = Visitor {
             link: program,
+            line,
             gen: Generator::new(),
         };
         for statement in ast {
@@ -40,7 +43,7 @@ impl<'a> Visitor<'a> {
 impl<'a> ast::Visitor for Visitor<'a> {
     fn visit_statement(&mut self, statement: &ast::Statement) {
         let mut link = Link::default();
-        let col = match self.gen.statement(&mut link, statement) {
+        let col = match self.gen.statement(&mut link, statement, self.line) {
             Ok(col) => col,
             Err(e) => {
                 self.link.error(e);
@@ -53,14 +56,15 @@ impl<'a> ast::Visitor for Visitor<'a> {
     }
     fn visit_variable(&mut self, var: &ast::Variable) {
         let mut link = Link::default();
-        let (col, name, len) = match self.gen.variable(&mut link, var) {
-            Ok((col, name, len)) => (col, name, len),
+        let (col, arg_col, name, len) = match self.gen.variable(&mut link, var) {
+            Ok((col, arg_col, name, len)) => (col, arg_col, name, len),
             Err(e) => {
                 self.link.error(e);
-                (0..0, "".into(), None)
+                (0..0, 0..0, "".into(), None)
             }
         };
-        let var_item = VarItem::new(col.clone(), name, link, len);
+        let external = self.link.extern_arity(&name);
+        let var_item = VarItem::new(col.clone(), arg_col, name, link, len, external);
         if let Some(error) = self.gen.var.push(var_item).err() {
             self.link.error(error.in_column(&col))
         }
@@ -83,25 +87,40 @@ impl<'a> ast::Visitor for Visitor<'a> {
 #[derive(Clone, Debug)]
 struct VarItem {
     col: Column,
+    arg_col: Column,
     name: Rc<str>,
     link: Link,
     arg_len: Option<usize>,
+    external: Option<RangeInclusive<usize>>,
 }
 
 impl VarItem {
-    fn new(col: Column, name: Rc<str>, link: Link, arg_len: Option<usize>) -> VarItem {
+    fn new(
+        col: Column,
+        arg_col: Column,
+        name: Rc<str>,
+        link: Link,
+        arg_len: Option<usize>,
+        external: Option<RangeInclusive<usize>>,
+    ) -> VarItem {
         VarItem {
             col,
+            arg_col,
             name,
             link,
             arg_len,
+            external,
         }
     }
 
     fn test_for_built_in(&self, strict: bool) -> Result<()> {
-        match Function::opcode_and_arity(&self.name) {
-            Some((_, range)) if range == (0..=0) && self.arg_len.is_some() && !strict => Ok(()),
-            Some((_, range)) if range != (0..=0) && self.arg_len.is_none() && !strict => Ok(()),
+        let arity = match Function::opcode_and_arity(&self.name) {
+            Some((_, range)) => Some(range),
+            None => self.external.clone(),
+        };
+        match arity {
+            Some(range) if range == (0..=0) && self.arg_len.is_some() && !strict => Ok(()),
+            Some(range) if range != (0..=0) && self.arg_len.is_none() && !strict => Ok(()),
             Some(_) => Err(error!(SyntaxError, ..&self.col; "RESERVED FOR BUILT-IN")),
             None => Ok(()),
         }
@@ -156,12 +175,28 @@ impl VarItem {
                     if arity.start() != arity.end() {
                         link.push(Opcode::Literal(Val::try_from(len)?))?;
                     }
-                    link.push(opcode)?;
+                    link.push_function(self.arg_col.clone(), opcode)?;
                     return Ok(self.col.clone());
                 }
                 return Err(error!(IllegalFunctionCall, ..&self.col; "WRONG NUMBER OF ARGUMENTS"));
             }
         }
+        if let Some(arity) = &self.external {
+            let len = match self.arg_len {
+                None if arity == &(0..=0) => 0,
+                None => {
+                    link.push(Opcode::Push(self.name))?;
+                    return Ok(self.col);
+                }
+                Some(len) => len,
+            };
+            if arity.contains(&len) {
+                link.push(Opcode::Literal(Val::try_from(len)?))?;
+                link.push_function(self.arg_col.clone(), Opcode::Extern(self.name))?;
+                return Ok(self.col);
+            }
+            return Err(error!(IllegalFunctionCall, ..&self.col; "WRONG NUMBER OF ARGUMENTS"));
+        }
         match self.arg_len {
             None => link.push(Opcode::Push(self.name))?,
             Some(len) => {
@@ -197,17 +232,22 @@ impl Generator {
         &mut self,
         link: &mut Link,
         var: &ast::Variable,
-    ) -> Result<(Column, Rc<str>, Option<usize>)> {
+    ) -> Result<(Column, Column, Rc<str>, Option<usize>)> {
         use ast::Variable;
-        let (col, ident, len) = match var {
-            Variable::Unary(col, ident) => (col, ident, None),
+        let (col, arg_col, ident, len) = match var {
+            Variable::Unary(col, ident) => (col, col.clone(), ident, None),
             Variable::Array(col, ident, vec_expr) => {
                 let len = vec_expr.len();
                 let vec_expr = self.expr.pop_n(len)?;
-                for (_col, ops) in vec_expr {
+                let mut arg_col = col.clone();
+                for (index, (expr_col, ops)) in vec_expr.into_iter().enumerate() {
+                    if index == 0 {
+                        arg_col.start = expr_col.start;
+                    }
+                    arg_col.end = expr_col.end;
                     link.append(ops)?
                 }
-                (col, ident, Some(len))
+                (col, arg_col, ident, Some(len))
             }
         };
         let s = match ident {
@@ -217,7 +257,7 @@ impl Generator {
             ast::Ident::Double(s) => s,
             ast::Ident::Integer(s) => s,
         };
-        Ok((col.clone(), s.clone(), len))
+        Ok((col.clone(), arg_col, s.clone(), len))
     }
 
     fn expression(&mut self, link: &mut Link, expr: &ast::Expression) -> Result<Column> {
@@ -274,13 +314,19 @@ impl Generator {
         }
     }
 
-    fn statement(&mut self, link: &mut Link, statement: &ast::Statement) -> Result<Column> {
+    fn statement(
+        &mut self,
+        link: &mut Link,
+        statement: &ast::Statement,
+        line: &Line,
+    ) -> Result<Column> {
         use ast::Statement;
         match statement {
             Statement::Clear(col, ..) => self.r#clear(link, col),
             Statement::Cls(col, ..) => self.r#cls(link, col),
             Statement::Cont(col, ..) => self.r#cont(link, col),
-            Statement::Data(col, v) => self.r#data(link, col, v.len()),
+            Statement::Data(col, v) => self.r#data(link, col, v.len(), line),
+            Statement::Date(col, ..) => self.r#date(link, col),
             Statement::Def(col, _, v, _) => self.r#def(link, col, v.len()),
             Statement::Defdbl(col, ..) => self.r#defdbl(link, col),
             Statement::Defint(col, ..) => self.r#defint(link, col),
@@ -288,7 +334,10 @@ impl Generator {
             Statement::Defstr(col, ..) => self.r#defstr(link, col),
             Statement::Delete(col, ..) => self.r#delete(link, col),
             Statement::Dim(col, v) => self.r#dim(link, col, v.len()),
-            Statement::End(col, ..) => self.r#end(link, col),
+            Statement::Do(col) => self.r#do(link, col, None),
+            Statement::DoUntil(col, ..) => self.r#do(link, col, Some(true)),
+            Statement::DoWhile(col, ..) => self.r#do(link, col, Some(false)),
+            Statement::End(col, expr) => self.r#end(link, col, expr.is_some()),
             Statement::Erase(col, v) => self.r#erase(link, col, v.len()),
             Statement::For(col, ..) => self.r#for(link, col),
             Statement::Gosub(col, ..) => self.r#gosub(link, col),
@@ -297,7 +346,12 @@ impl Generator {
             Statement::Input(col, _, _, v) => self.r#input(link, col, v.len()),
             Statement::Let(col, ..) => self.r#let(link, col),
             Statement::List(col, ..) => self.r#list(link, col),
+            Statement::ExitFor(col) => self.r#exit_for(link, col),
+            Statement::ExitWhile(col) => self.r#exit_while(link, col),
             Statement::Load(col, ..) => self.r#load(link, col),
+            Statement::Loop(col) => self.r#loop(link, col, None),
+            Statement::LoopUntil(col, ..) => self.r#loop(link, col, Some(true)),
+            Statement::LoopWhile(col, ..) => self.r#loop(link, col, Some(false)),
             Statement::Mid(col, ..) => self.r#mid(link, col),
             Statement::New(col, ..) => self.r#new_(link, col),
             Statement::Next(col, v) => self.r#next(link, col, v.len()),
@@ -312,8 +366,10 @@ impl Generator {
             Statement::Save(col, ..) => self.r#save(link, col),
             Statement::Stop(col, ..) => self.r#stop(link, col),
             Statement::Swap(col, ..) => self.r#swap(link, col),
+            Statement::Time(col, ..) => self.r#time(link, col),
             Statement::Troff(col, ..) => self.r#troff(link, col),
             Statement::Tron(col, ..) => self.r#tron(link, col),
+            Statement::Wait(col, ..) => self.r#wait(link, col),
             Statement::Wend(col, ..) => self.r#wend(link, col),
             Statement::While(col, ..) => self.r#while(link, col),
         }
@@ -333,8 +389,10 @@ impl Generator {
     }
 
     fn r#cls(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        let (sub_col, expr) = self.expr.pop()?;
+        link.append(expr)?;
         link.push(Opcode::Cls)?;
-        Ok(col.clone())
+        Ok(col.start..sub_col.end)
     }
 
     fn r#cont(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
@@ -342,15 +400,22 @@ impl Generator {
         Ok(col.clone())
     }
 
-    fn r#data(&mut self, link: &mut Link, col: &Column, len: usize) -> Result<Column> {
+    fn r#data(&mut self, link: &mut Link, col: &Column, len: usize, line: &Line) -> Result<Column> {
         let exprs = self.expr.pop_n(len)?;
         for (expr_col, mut expr_link) in exprs {
-            expr_link.transform_to_data(&expr_col)?;
+            expr_link.transform_to_data(&expr_col, line.text_at(&expr_col).into())?;
             link.append(expr_link)?;
         }
         Ok(col.clone())
     }
 
+    fn r#date(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        let (expr_col, expr_link) = self.expr.pop()?;
+        link.append(expr_link)?;
+        link.push(Opcode::LetDate)?;
+        Ok(col.start..expr_col.end)
+    }
+
     fn r#def(&mut self, link: &mut Link, col: &Column, len: usize) -> Result<Column> {
         let mut vars = self.var.pop_n(len)?;
         let fn_name = self.var.pop()?;
@@ -421,7 +486,27 @@ impl Generator {
         Ok(col)
     }
 
-    fn r#end(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+    fn r#do(&mut self, link: &mut Link, col: &Column, until: Option<bool>) -> Result<Column> {
+        match until {
+            None => {
+                link.push_do(col.clone(), None, false)?;
+                Ok(col.clone())
+            }
+            Some(until) => {
+                let (sub_col, expr) = self.expr.pop()?;
+                link.push_do(col.clone(), Some(expr), until)?;
+                Ok(col.start..sub_col.end)
+            }
+        }
+    }
+
+    fn r#end(&mut self, link: &mut Link, col: &Column, exit: bool) -> Result<Column> {
+        if exit {
+            let (sub_col, expr) = self.expr.pop()?;
+            link.append(expr)?;
+            link.push(Opcode::Exit)?;
+            return Ok(col.start..sub_col.end);
+        }
         link.push(Opcode::End)?;
         Ok(col.clone())
     }
@@ -433,6 +518,16 @@ impl Generator {
         Ok(col.clone())
     }
 
+    fn r#exit_for(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        link.push_exit_for(col.clone())?;
+        Ok(col.clone())
+    }
+
+    fn r#exit_while(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        link.push_exit_while(col.clone())?;
+        Ok(col.clone())
+    }
+
     fn r#for(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
         let (step_col, step_ops) = self.expr.pop()?;
         let (_to_col, to_ops) = self.expr.pop()?;
@@ -542,6 +637,20 @@ impl Generator {
         Ok(col.start..expr_col.end)
     }
 
+    fn r#loop(&mut self, link: &mut Link, col: &Column, until: Option<bool>) -> Result<Column> {
+        match until {
+            None => {
+                link.push_loop(col.clone(), None, false)?;
+                Ok(col.clone())
+            }
+            Some(until) => {
+                let (sub_col, expr) = self.expr.pop()?;
+                link.push_loop(col.clone(), Some(expr), until)?;
+                Ok(col.start..sub_col.end)
+            }
+        }
+    }
+
     fn r#new_(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
         link.push(Opcode::New)?;
         Ok(col.clone())
@@ -550,7 +659,7 @@ impl Generator {
     fn r#next(&mut self, link: &mut Link, col: &Column, len: usize) -> Result<Column> {
         for var in self.var.pop_n(len)? {
             var.test_for_built_in(false)?;
-            link.push(Opcode::Next(var.name))?;
+            link.push_next(col.clone(), var.name)?;
         }
         Ok(col.clone())
     }
@@ -596,7 +705,7 @@ impl Generator {
 
     fn r#read(&mut self, link: &mut Link, col: &Column, len: usize) -> Result<Column> {
         for var in self.var.pop_n(len)? {
-            link.push(Opcode::Read)?;
+            link.push(Opcode::Read(var.name.clone()))?;
             var.push_as_pop(link)?;
         }
         Ok(col.clone())
@@ -673,6 +782,13 @@ impl Generator {
         Ok(col.clone())
     }
 
+    fn r#time(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        let (expr_col, expr_link) = self.expr.pop()?;
+        link.append(expr_link)?;
+        link.push(Opcode::LetTime)?;
+        Ok(col.start..expr_col.end)
+    }
+
     fn r#troff(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
         link.push(Opcode::Troff)?;
         Ok(col.clone())
@@ -683,6 +799,17 @@ impl Generator {
         Ok(col.clone())
     }
 
+    fn r#wait(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
+        let (_xor_col, xor_link) = self.expr.pop()?;
+        let (mask_col, mask_link) = self.expr.pop()?;
+        let (_port_col, port_link) = self.expr.pop()?;
+        link.append(port_link)?;
+        link.append(mask_link)?;
+        link.append(xor_link)?;
+        link.push(Opcode::Wait)?;
+        Ok(col.start..mask_col.end)
+    }
+
     fn r#wend(&mut self, link: &mut Link, col: &Column) -> Result<Column> {
         link.push_wend(col.clone())?;
         Ok(col.clone())
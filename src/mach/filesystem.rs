@@ -0,0 +1,11 @@
+use crate::lang::Error;
+
+/// ## Virtual file system
+///
+/// Hosts without real files, such as WASM, implement this so that
+/// `LOAD`, `SAVE`, and `RUN "<filename>"` resolve inside the runtime
+/// instead of being delivered as events.
+pub trait FileSystem {
+    fn read(&self, name: &str) -> Result<String, Error>;
+    fn write(&mut self, name: &str, data: &str) -> Result<(), Error>;
+}
@@ -2,6 +2,7 @@ extern crate chrono;
 use super::{Opcode, Stack, Val};
 use crate::error;
 use crate::lang::Error;
+use chrono::NaiveDateTime;
 use std::convert::TryFrom;
 use std::rc::Rc;
 
@@ -9,6 +10,17 @@ type Result<T> = std::result::Result<T, Error>;
 
 pub struct Function {}
 
+/// ## Random number generators for `RND`
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RndAlgo {
+    /// Wichmann–Hill combination of three generators. The default.
+    #[default]
+    WichmannHill,
+    /// 24-bit linear congruential generator used by GW-BASIC.
+    GwBasic,
+}
+
 impl Function {
     pub fn opcode_and_arity(func_name: &str) -> Option<(Opcode, std::ops::RangeInclusive<usize>)> {
         match func_name {
@@ -64,7 +76,7 @@ impl Function {
         match string.chars().next() {
             Some(ch) => {
                 let num = u32::from(ch);
-                if num <= i16::max_value() as u32 {
+                if num <= i16::MAX as u32 {
                     Ok(Val::Integer(num as i16))
                 } else if num <= 16_777_216 {
                     Ok(Val::Single(num as f32))
@@ -104,7 +116,13 @@ impl Function {
     }
 
     pub fn cint(val: Val) -> Result<Val> {
-        Ok(Val::Integer(i16::try_from(val)?))
+        use Val::*;
+        let val = match val {
+            Single(n) => Single(n.round_ties_even()),
+            Double(n) => Double(n.round_ties_even()),
+            _ => val,
+        };
+        Ok(Integer(i16::try_from(val)?))
     }
 
     pub fn cos(val: Val) -> Result<Val> {
@@ -127,19 +145,22 @@ impl Function {
         }
     }
 
-    pub fn date() -> Result<Val> {
-        Ok(Val::String(
-            chrono::Local::now().format("%m-%d-%Y").to_string().into(),
-        ))
+    pub fn date(now: NaiveDateTime) -> Result<Val> {
+        Ok(Val::String(now.format("%m-%d-%Y").to_string().into()))
     }
 
     pub fn exp(val: Val) -> Result<Val> {
         use Val::*;
+        let val = match val {
+            Integer(n) => Single((n as f32).exp()),
+            Single(n) => Single(n.exp()),
+            Double(n) => Double(n.exp()),
+            String(_) | Return(_) | Next(_) => return Err(error!(TypeMismatch)),
+        };
         match val {
-            Integer(n) => Ok(Single((n as f32).exp())),
-            Single(n) => Ok(Single(n.exp())),
-            Double(n) => Ok(Double(n.exp())),
-            String(_) | Return(_) | Next(_) => Err(error!(TypeMismatch)),
+            Single(n) if n.is_infinite() => Err(error!(Overflow)),
+            Double(n) if n.is_infinite() => Err(error!(Overflow)),
+            _ => Ok(val),
         }
     }
 
@@ -153,8 +174,16 @@ impl Function {
         }
     }
 
+    /// Any signed or unsigned 16-bit value, as taken by `HEX$` and `OCT$`.
+    fn word(val: Val) -> Result<u16> {
+        match i16::try_from(val.clone()) {
+            Ok(num) => Ok(num as u16),
+            Err(_) => u16::try_from(val),
+        }
+    }
+
     pub fn hex(val: Val) -> Result<Val> {
-        let num = i16::try_from(val)?;
+        let num = Function::word(val)?;
         Ok(Val::String(format!("{:X}", num).into()))
     }
 
@@ -217,6 +246,9 @@ impl Function {
     pub fn log(val: Val) -> Result<Val> {
         use Val::*;
         match val {
+            Integer(n) if n <= 0 => Err(error!(IllegalFunctionCall)),
+            Single(n) if n <= 0.0 => Err(error!(IllegalFunctionCall)),
+            Double(n) if n <= 0.0 => Err(error!(IllegalFunctionCall)),
             Integer(n) => Ok(Single((n as f32).ln())),
             Single(n) => Ok(Single(n.ln())),
             Double(n) => Ok(Double(n.ln())),
@@ -245,12 +277,12 @@ impl Function {
                     }
                 }
             },
-            None => Ok(Val::String(string)),
+            None => Ok(Val::String("".into())),
         }
     }
 
     pub fn oct(val: Val) -> Result<Val> {
-        let num = i16::try_from(val)?;
+        let num = Function::word(val)?;
         Ok(Val::String(format!("{:o}", num).into()))
     }
 
@@ -273,7 +305,7 @@ impl Function {
         }
     }
 
-    pub fn rnd(st: &mut (u32, u32, u32), mut vec_val: Stack<Val>) -> Result<Val> {
+    pub fn rnd(algo: RndAlgo, st: &mut (u32, u32, u32), mut vec_val: Stack<Val>) -> Result<Val> {
         let val = match vec_val.pop() {
             Ok(s) => f32::try_from(s)?,
             Err(_) => 1.0,
@@ -284,14 +316,24 @@ impl Function {
             st.1 = seed;
             st.2 = seed;
         }
-        if val != 0.0 {
-            st.0 = (171 * st.0) % 30269;
-            st.1 = (172 * st.1) % 30307;
-            st.2 = (170 * st.2) % 30323;
+        match algo {
+            RndAlgo::WichmannHill => {
+                if val != 0.0 {
+                    st.0 = (171 * st.0) % 30269;
+                    st.1 = (172 * st.1) % 30307;
+                    st.2 = (170 * st.2) % 30323;
+                }
+                Ok(Val::Single(
+                    (st.0 as f32 / 30269.0 + st.1 as f32 / 30307.0 + st.2 as f32 / 30323.0) % 1.0,
+                ))
+            }
+            RndAlgo::GwBasic => {
+                if val != 0.0 {
+                    st.0 = st.0.wrapping_mul(214_013).wrapping_add(2_531_011) & 0x_00FF_FFFF;
+                }
+                Ok(Val::Single(st.0 as f32 / 16_777_216.0))
+            }
         }
-        Ok(Val::Single(
-            (st.0 as f32 / 30269.0 + st.1 as f32 / 30307.0 + st.2 as f32 / 30323.0) % 1.0,
-        ))
     }
 
     pub fn sgn(val: Val) -> Result<Val> {
@@ -343,6 +385,9 @@ impl Function {
     pub fn sqr(val: Val) -> Result<Val> {
         use Val::*;
         match val {
+            Integer(n) if n < 0 => Err(error!(IllegalFunctionCall)),
+            Single(n) if n < 0.0 => Err(error!(IllegalFunctionCall)),
+            Double(n) if n < 0.0 => Err(error!(IllegalFunctionCall)),
             Integer(n) => Ok(Single((n as f32).sqrt())),
             Single(n) => Ok(Single(n.sqrt())),
             Double(n) => Ok(Double(n.sqrt())),
@@ -364,10 +409,16 @@ impl Function {
             return Err(error!(Overflow));
         }
         let ch = match ch {
-            Val::String(s) => match s.chars().next() {
-                Some(ch) => ch,
-                None => return Err(error!(IllegalFunctionCall)),
-            },
+            Val::String(s) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => ch,
+                    (Some(_), Some(_)) => {
+                        return Err(error!(IllegalFunctionCall; "EXPECTED SINGLE CHAR"))
+                    }
+                    (None, _) => return Err(error!(IllegalFunctionCall)),
+                }
+            }
             _ => {
                 let num = u32::try_from(ch)?;
                 match char::try_from(num) {
@@ -387,10 +438,8 @@ impl Function {
         let len = if tab < 0 {
             let tab = -tab as usize;
             tab - (print_col % tab)
-        } else if tab as usize > print_col {
-            tab as usize - print_col
         } else {
-            0
+            (tab as usize).saturating_sub(print_col)
         };
         Ok(Val::String(" ".repeat(len).into()))
     }
@@ -405,10 +454,8 @@ impl Function {
         }
     }
 
-    pub fn time() -> Result<Val> {
-        Ok(Val::String(
-            chrono::Local::now().format("%H:%M:%S").to_string().into(),
-        ))
+    pub fn time(now: NaiveDateTime) -> Result<Val> {
+        Ok(Val::String(now.format("%H:%M:%S").to_string().into()))
     }
 
     pub fn val(val: Val) -> Result<Val> {
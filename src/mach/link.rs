@@ -1,3 +1,4 @@
+use super::bytecode::{Reader, Writer};
 use super::{Address, Opcode, Operation, Stack, Symbol, Val};
 use crate::error;
 use crate::lang::{Column, Error, LineNumber, MaxValue};
@@ -14,11 +15,31 @@ pub struct Link {
     current_symbol: Symbol,
     ops: Stack<Opcode>,
     data: Stack<Val>,
+    data_lines: Vec<LineNumber>,
+    data_text: Vec<Rc<str>>,
     data_pos: Address,
     direct_set: bool,
     symbols: BTreeMap<Symbol, (Address, Address)>,
     unlinked: HashMap<Address, (Column, Symbol)>,
-    whiles: Vec<(bool, Column, Address, Symbol)>,
+    columns: HashMap<Address, Column>,
+    loops: Vec<(LoopMark, Column, Address, Symbol)>,
+    undefined_lines: Vec<(u16, u16)>,
+}
+
+/// Loop statements matched by position in the link phase.
+/// `DoTest` is a `DO WHILE` or `DO UNTIL` with an exit jump to link.
+/// `FOR` loops run from the stack, so only `EXIT FOR` depends on this.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LoopMark {
+    While,
+    Wend,
+    ExitWhile,
+    Do,
+    DoTest,
+    Loop,
+    For,
+    Next,
+    ExitFor,
 }
 
 impl Default for Link {
@@ -27,11 +48,15 @@ impl Default for Link {
             current_symbol: 0,
             ops: Stack::new("PROGRAM SIZE LIMIT EXCEEDED"),
             data: Stack::new("DATA SIZE LIMIT EXCEEDED"),
+            data_lines: Vec::default(),
+            data_text: Vec::default(),
             data_pos: 0,
             direct_set: false,
             symbols: BTreeMap::default(),
             unlinked: HashMap::default(),
-            whiles: Vec::default(),
+            columns: HashMap::default(),
+            loops: Vec::default(),
+            undefined_lines: Vec::default(),
         }
     }
 }
@@ -62,12 +87,19 @@ impl Link {
             self.unlinked
                 .insert(address + ops_addr_offset, (col.clone(), symbol));
         }
-        for (kind, col, addr, sym) in link.whiles {
-            self.whiles
+        for (address, col) in link.columns {
+            self.columns.insert(address + ops_addr_offset, col);
+        }
+        for (kind, col, addr, sym) in link.loops {
+            self.loops
                 .push((kind, col, addr + ops_addr_offset, sym + sym_offset));
         }
         self.current_symbol += link.current_symbol;
         self.ops.append(&mut link.ops)?;
+        let line_number = self.line_number_for(ops_addr_offset);
+        self.data_lines
+            .extend(std::iter::repeat_n(line_number, link.data.len()));
+        self.data_text.append(&mut link.data_text);
         self.data.append(&mut link.data)
     }
 
@@ -75,10 +107,21 @@ impl Link {
         self.ops.push(op)
     }
 
-    pub fn transform_to_data(&mut self, col: &Column) -> Result<()> {
+    pub fn push_function(&mut self, col: Column, op: Opcode) -> Result<()> {
+        self.columns.insert(self.ops.len(), col);
+        self.ops.push(op)
+    }
+
+    pub fn column_for(&self, op_addr: Address) -> Option<Column> {
+        self.columns.get(&op_addr).cloned()
+    }
+
+    /// Move a literal to `DATA` along with its source text.
+    pub fn transform_to_data(&mut self, col: &Column, text: Rc<str>) -> Result<()> {
         if self.ops.len() == 1 {
             if let Some(Opcode::Literal(val)) = self.ops.drain(..).next() {
                 self.data.push(val)?;
+                self.data_text.push(text);
                 return Ok(());
             }
         } else if self.ops.len() == 2 {
@@ -86,6 +129,7 @@ impl Link {
             if let Some(Opcode::Literal(val)) = expr_link.next() {
                 if let Some(Opcode::Neg) = expr_link.next() {
                     self.data.push(Operation::negate(val)?)?;
+                    self.data_text.push(text);
                     return Ok(());
                 }
             }
@@ -93,10 +137,17 @@ impl Link {
         Err(error!(SyntaxError, ..col; "EXPECTED LITERAL"))
     }
 
-    pub fn read_data(&mut self) -> Result<Val> {
+    /// Next `DATA` value, its source text, and the line number it came from.
+    pub fn read_data(&mut self) -> Result<(Val, Rc<str>, LineNumber)> {
         if let Some(val) = self.data.get(self.data_pos) {
+            let text = self
+                .data_text
+                .get(self.data_pos)
+                .cloned()
+                .unwrap_or_default();
+            let line_number = self.data_lines.get(self.data_pos).cloned().flatten();
             self.data_pos += 1;
-            Ok(val.clone())
+            Ok((val.clone(), text, line_number))
         } else {
             Err(error!(OutOfData))
         }
@@ -106,6 +157,10 @@ impl Link {
         self.data_pos = addr;
     }
 
+    pub fn data_pos(&self) -> Address {
+        self.data_pos
+    }
+
     pub fn get(&self, addr: Address) -> Option<&Opcode> {
         self.ops.get(addr)
     }
@@ -118,6 +173,7 @@ impl Link {
     where
         R: std::ops::RangeBounds<usize>,
     {
+        self.columns.retain(|addr, _| !range.contains(addr));
         self.ops.drain(range)
     }
 
@@ -129,13 +185,75 @@ impl Link {
         self.ops.len()
     }
 
+    pub fn data_len(&self) -> usize {
+        self.data.len()
+    }
+
     pub fn clear(&mut self) {
         self.current_symbol = 0;
         self.direct_set = false;
         self.ops.clear();
         self.data.clear();
+        self.data_lines.clear();
+        self.data_text.clear();
         self.symbols.clear();
+        self.undefined_lines.clear();
         self.unlinked.clear();
+        self.columns.clear();
+    }
+
+    /// Write the linked ops below `end` with their data, line symbols, and columns.
+    pub fn serialize(&self, w: &mut Writer, end: Address) {
+        let ops: Vec<&Opcode> = (0..end).filter_map(|addr| self.ops.get(addr)).collect();
+        w.usize(ops.len());
+        ops.iter().for_each(|op| w.opcode(op));
+        w.usize(self.data.len());
+        for addr in 0..self.data.len() {
+            if let Some(val) = self.data.get(addr) {
+                w.val(val);
+            }
+            w.line_number(self.data_lines.get(addr).cloned().flatten());
+            w.string(&self.data_text.get(addr).cloned().unwrap_or_default());
+        }
+        let symbols: Vec<_> = self
+            .symbols
+            .range(0..=LineNumber::max_value() as Symbol)
+            .collect();
+        w.usize(symbols.len());
+        for (symbol, (op_addr, data_addr)) in symbols {
+            w.isize(*symbol);
+            w.usize(*op_addr);
+            w.usize(*data_addr);
+        }
+        let mut columns: Vec<_> = self.columns.iter().filter(|(a, _)| **a < end).collect();
+        columns.sort_by_key(|(addr, _)| **addr);
+        w.usize(columns.len());
+        for (addr, col) in columns {
+            w.usize(*addr);
+            w.column(col);
+        }
+    }
+
+    pub fn deserialize(r: &mut Reader) -> Result<Link> {
+        let mut link = Link::default();
+        for _ in 0..r.usize()? {
+            link.ops.push(r.opcode()?)?;
+        }
+        for _ in 0..r.usize()? {
+            link.data.push(r.val()?)?;
+            link.data_lines.push(r.line_number()?);
+            link.data_text.push(r.string()?);
+        }
+        for _ in 0..r.usize()? {
+            let symbol = r.isize()?;
+            link.symbols.insert(symbol, (r.usize()?, r.usize()?));
+        }
+        for _ in 0..r.usize()? {
+            let addr = r.usize()?;
+            link.columns.insert(addr, r.column()?);
+        }
+        link.set_start_of_direct(link.ops.len());
+        Ok(link)
     }
 
     pub fn next_symbol(&mut self) -> Symbol {
@@ -173,12 +291,32 @@ impl Link {
 
     pub fn push_for(&mut self, col: Column) -> Result<()> {
         let next = self.next_symbol();
-        self.unlinked.insert(self.ops.len(), (col, next));
+        self.unlinked.insert(self.ops.len(), (col.clone(), next));
+        self.loops.push((LoopMark::For, col, self.ops.len(), next));
         self.ops.push(Opcode::Literal(Val::Next(0)))?;
         self.push_symbol(next);
         Ok(())
     }
 
+    pub fn push_next(&mut self, col: Column, var_name: Rc<str>) -> Result<()> {
+        let sym = self.next_symbol();
+        self.push(Opcode::Next(var_name))?;
+        self.loops.push((LoopMark::Next, col, self.ops.len(), sym));
+        self.push_symbol(sym);
+        Ok(())
+    }
+
+    pub fn push_exit_for(&mut self, col: Column) -> Result<()> {
+        self.loops.push((LoopMark::ExitFor, col, self.ops.len(), 0));
+        self.push(Opcode::ExitFor(0))
+    }
+
+    pub fn push_exit_while(&mut self, col: Column) -> Result<()> {
+        self.loops
+            .push((LoopMark::ExitWhile, col, self.ops.len(), 0));
+        self.push(Opcode::Jump(0))
+    }
+
     pub fn push_gosub(&mut self, col: Column, line_number: LineNumber) -> Result<()> {
         let ret_sym = self.next_symbol();
         self.push_return_val(col.clone(), ret_sym)?;
@@ -240,7 +378,7 @@ impl Link {
     pub fn push_wend(&mut self, col: Column) -> Result<()> {
         let sym = self.next_symbol();
         let addr = self.ops.len();
-        self.whiles.push((false, col, addr, sym));
+        self.loops.push((LoopMark::Wend, col, addr, sym));
         self.push(Opcode::Jump(0))?;
         self.push_symbol(sym);
         Ok(())
@@ -250,10 +388,62 @@ impl Link {
         let sym = self.next_symbol();
         self.push_symbol(sym);
         self.append(expr)?;
-        self.whiles.push((true, col, self.ops.len(), sym));
+        self.loops.push((LoopMark::While, col, self.ops.len(), sym));
         self.push(Opcode::IfNot(0))
     }
 
+    pub fn push_do(&mut self, col: Column, expr: Option<Link>, until: bool) -> Result<()> {
+        let sym = self.next_symbol();
+        self.push_symbol(sym);
+        let expr = match expr {
+            Some(expr) => expr,
+            None => {
+                self.loops.push((LoopMark::Do, col, self.ops.len(), sym));
+                return Ok(());
+            }
+        };
+        self.append(expr)?;
+        if until {
+            let body_sym = self.next_symbol();
+            self.unlinked
+                .insert(self.ops.len(), (col.clone(), body_sym));
+            self.push(Opcode::IfNot(0))?;
+            self.loops
+                .push((LoopMark::DoTest, col, self.ops.len(), sym));
+            self.push(Opcode::Jump(0))?;
+            self.push_symbol(body_sym);
+            Ok(())
+        } else {
+            self.loops
+                .push((LoopMark::DoTest, col, self.ops.len(), sym));
+            self.push(Opcode::IfNot(0))
+        }
+    }
+
+    pub fn push_loop(&mut self, col: Column, expr: Option<Link>, until: bool) -> Result<()> {
+        let sym = self.next_symbol();
+        match expr {
+            None => {
+                self.loops.push((LoopMark::Loop, col, self.ops.len(), sym));
+                self.push(Opcode::Jump(0))?;
+            }
+            Some(expr) => {
+                self.append(expr)?;
+                if until {
+                    self.loops.push((LoopMark::Loop, col, self.ops.len(), sym));
+                    self.push(Opcode::IfNot(0))?;
+                } else {
+                    self.unlinked.insert(self.ops.len(), (col.clone(), sym));
+                    self.push(Opcode::IfNot(0))?;
+                    self.loops.push((LoopMark::Loop, col, self.ops.len(), sym));
+                    self.push(Opcode::Jump(0))?;
+                }
+            }
+        }
+        self.push_symbol(sym);
+        Ok(())
+    }
+
     pub fn set_start_of_direct(&mut self, op_addr: Address) {
         self.direct_set = true;
         self.symbols.insert(
@@ -275,36 +465,103 @@ impl Link {
         None
     }
 
-    fn link_whiles(&mut self) -> Vec<Error> {
+    fn link_loops(&mut self) -> Vec<Error> {
         let mut errors: Vec<Error> = vec![];
-        let mut whiles: Vec<(Column, Address, Symbol)> = Vec::default();
-        for (kind, col, addr, sym) in std::mem::take(&mut self.whiles).drain(..) {
-            if kind {
-                whiles.push((col, addr, sym));
-                continue;
-            }
+        let mut whiles: Vec<(LoopMark, Column, Address, Symbol)> = Vec::default();
+        let mut while_exits: Vec<(usize, Column, Address)> = Vec::default();
+        let mut fors: Vec<Vec<(Column, Address)>> = Vec::default();
+        for (mark, col, addr, sym) in std::mem::take(&mut self.loops).drain(..) {
+            let opener = match mark {
+                LoopMark::While | LoopMark::Do | LoopMark::DoTest => {
+                    whiles.push((mark, col, addr, sym));
+                    continue;
+                }
+                LoopMark::ExitWhile => {
+                    match whiles.iter().rposition(|(m, ..)| *m == LoopMark::While) {
+                        Some(depth) => while_exits.push((depth, col, addr)),
+                        None => errors.push(error!(
+                            WendWithoutWhile,
+                            self.line_number_for(addr),
+                            ..&col
+                        )),
+                    }
+                    continue;
+                }
+                LoopMark::For => {
+                    fors.push(vec![]);
+                    continue;
+                }
+                LoopMark::Next => {
+                    for (exit_col, exit_addr) in fors.pop().unwrap_or_default() {
+                        self.unlinked.insert(exit_addr, (exit_col, sym));
+                    }
+                    continue;
+                }
+                LoopMark::ExitFor => {
+                    match fors.last_mut() {
+                        Some(exits) => exits.push((col, addr)),
+                        None => {
+                            errors.push(error!(NextWithoutFor, self.line_number_for(addr), ..&col))
+                        }
+                    }
+                    continue;
+                }
+                LoopMark::Wend => &[LoopMark::While][..],
+                LoopMark::Loop => &[LoopMark::Do, LoopMark::DoTest][..],
+            };
             match whiles.pop() {
-                None => errors.push(error!(WendWithoutWhile, self.line_number_for(addr), ..&col)),
-                Some((wh_col, wh_addr, wh_sym)) => {
-                    self.unlinked.insert(wh_addr, (wh_col.clone(), sym));
+                Some((wh_mark, wh_col, wh_addr, wh_sym)) if opener.contains(&wh_mark) => {
+                    if wh_mark != LoopMark::Do {
+                        self.unlinked.insert(wh_addr, (wh_col.clone(), sym));
+                    }
                     self.unlinked.insert(addr, (col, wh_sym));
+                    let depth = whiles.len();
+                    for (_, exit_col, exit_addr) in while_exits.iter().filter(|e| e.0 == depth) {
+                        self.unlinked.insert(*exit_addr, (exit_col.clone(), sym));
+                    }
+                    while_exits.retain(|e| e.0 != depth);
+                }
+                unmatched => {
+                    whiles.extend(unmatched);
+                    let line_number = self.line_number_for(addr);
+                    errors.push(match mark {
+                        LoopMark::Wend => error!(WendWithoutWhile, line_number, ..&col),
+                        _ => error!(LoopWithoutDo, line_number, ..&col),
+                    });
                 }
             }
         }
-        while let Some((col, addr, _)) = whiles.pop() {
-            errors.push(error!(WhileWithoutWend, self.line_number_for(addr), ..&col));
+        while let Some((mark, col, addr, _)) = whiles.pop() {
+            let line_number = self.line_number_for(addr);
+            errors.push(match mark {
+                LoopMark::While => error!(WhileWithoutWend, line_number, ..&col),
+                _ => error!(DoWithoutLoop, line_number, ..&col),
+            });
+        }
+        for (col, addr) in fors.drain(..).flatten() {
+            errors.push(error!(ForWithoutNext, self.line_number_for(addr), ..&col));
         }
         errors
     }
 
+    /// Pairs of (referencing line, missing line) found while linking.
+    pub fn undefined_lines(&self) -> Vec<(u16, u16)> {
+        let mut undefined_lines = self.undefined_lines.clone();
+        undefined_lines.sort_unstable();
+        undefined_lines
+    }
+
     pub fn link(&mut self) -> Vec<Error> {
-        let mut errors = self.link_whiles();
+        let mut errors = self.link_loops();
         for (op_addr, (col, symbol)) in std::mem::take(&mut self.unlinked) {
             match self.symbols.get(&symbol) {
                 None => {
                     if symbol >= 0 {
-                        let error = error!(UndefinedLine, self.line_number_for(op_addr), ..&col);
-                        errors.push(error);
+                        let line_number = self.line_number_for(op_addr);
+                        if let Some(line_number) = line_number {
+                            self.undefined_lines.push((line_number, symbol as u16));
+                        }
+                        errors.push(error!(UndefinedLine, line_number, ..&col));
                         continue;
                     }
                 }
@@ -313,6 +570,7 @@ impl Link {
                         if let Some(new_op) = match op {
                             Opcode::IfNot(_) => Some(Opcode::IfNot(*op_dest)),
                             Opcode::Jump(_) => Some(Opcode::Jump(*op_dest)),
+                            Opcode::ExitFor(_) => Some(Opcode::ExitFor(*op_dest)),
                             Opcode::Literal(Val::Return(_)) => {
                                 Some(Opcode::Literal(Val::Return(*op_dest)))
                             }
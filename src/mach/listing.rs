@@ -1,10 +1,14 @@
+extern crate crc;
 use super::MAX_LINE_LEN;
 use crate::error;
 use crate::lang::{Column, Error, Line, LineNumber, MaxValue};
+use crc::Hasher32;
 use std::collections::{btree_map::Values, BTreeMap, HashMap};
 use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 
+/// ## Program source
+
 #[derive(Debug, Clone, Default)]
 pub struct Listing {
     source: Arc<BTreeMap<LineNumber, Line>>,
@@ -12,6 +16,30 @@ pub struct Listing {
     pub direct_errors: Arc<Vec<Error>>,
 }
 
+/// ## Instructions from a patch file for the host
+///
+/// Each `index` is the zero-based line of the patch file it came from.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchAction {
+    /// A comment to display.
+    Message(String),
+    /// Retrieve `url`, apply with `Listing::patch`, and save to `filename`.
+    Retrieve {
+        index: usize,
+        filename: String,
+        crc: u32,
+        url: String,
+        lines: Vec<(usize, String)>,
+    },
+    /// Save only the patch lines to `filename`.
+    Create {
+        index: usize,
+        filename: String,
+        lines: Vec<(usize, String)>,
+    },
+}
+
 impl Listing {
     pub fn clear(&mut self) {
         self.source = Arc::default();
@@ -24,13 +52,11 @@ impl Listing {
     }
 
     pub fn insert(&mut self, line: Line) -> Option<Line> {
-        Arc::get_mut(&mut self.source)
-            .unwrap()
-            .insert(line.number(), line)
+        Arc::make_mut(&mut self.source).insert(line.number(), line)
     }
 
     pub fn remove(&mut self, ln: LineNumber) -> Option<Line> {
-        Arc::get_mut(&mut self.source).unwrap().remove(&ln)
+        Arc::make_mut(&mut self.source).remove(&ln)
     }
 
     pub fn remove_range(&mut self, range: RangeInclusive<LineNumber>) -> bool {
@@ -42,7 +68,7 @@ impl Listing {
         if to_remove.is_empty() {
             return false;
         }
-        let source = Arc::get_mut(&mut self.source).unwrap();
+        let source = Arc::make_mut(&mut self.source);
         for line_number in to_remove {
             source.remove(&line_number);
         }
@@ -69,9 +95,7 @@ impl Listing {
         let line = Line::new(line);
         if line.is_empty() {
             if !line.is_direct() {
-                Arc::get_mut(&mut self.source)
-                    .unwrap()
-                    .remove(&line.number());
+                Arc::make_mut(&mut self.source).remove(&line.number());
             }
             Ok(())
         } else if line.is_direct() {
@@ -82,6 +106,164 @@ impl Listing {
         }
     }
 
+    /// Used for loading a file which may be a patch. A patch begins with
+    /// a comment `'` or file `"` line. Anything else is loaded as a program.
+    pub fn apply_patch(&mut self, patch: &str) -> Result<Vec<PatchAction>, Error> {
+        if !patch.starts_with('"') && !patch.starts_with('\'') {
+            for (index, line) in Listing::logical_lines(patch) {
+                if let Err(error) = self.load_str(&line) {
+                    return Err(error.message(&format!("In line {} of the file.", index + 1)));
+                }
+            }
+            return Ok(vec![]);
+        }
+        let mut actions: Vec<PatchAction> = vec![];
+        for (index, line) in patch.lines().enumerate() {
+            if let Some(comment) = line.strip_prefix('\'') {
+                actions.push(PatchAction::Message(comment.trim().to_string()));
+                continue;
+            }
+            if line.starts_with('"') {
+                let mut parts: Vec<&str> = line.split_ascii_whitespace().collect();
+                if parts.len() == 1 {
+                    let filename = Listing::patch_filename(parts[0], index)?;
+                    actions.push(PatchAction::Create {
+                        index,
+                        filename,
+                        lines: vec![],
+                    });
+                } else if parts.len() == 3 {
+                    let url = parts.pop().unwrap_or_default().to_string();
+                    let crc = match u32::from_str_radix(parts.pop().unwrap_or_default(), 16) {
+                        Ok(crc) => crc,
+                        Err(_) => {
+                            return Err(error!(SyntaxError; &format!(
+                                "Unable to parse crc info in line {} of the patch file.",
+                                index + 1
+                            )));
+                        }
+                    };
+                    let filename = Listing::patch_filename(parts[0], index)?;
+                    actions.push(PatchAction::Retrieve {
+                        index,
+                        filename,
+                        crc,
+                        url,
+                        lines: vec![],
+                    });
+                } else {
+                    return Err(error!(SyntaxError; &format!(
+                        "Unable to parse info in line {} of the patch file.",
+                        index + 1
+                    )));
+                }
+                continue;
+            }
+            if line.len() > MAX_LINE_LEN {
+                return Err(error!(LineBufferOverflow; &format!(
+                    "In line {} of the patch file.",
+                    index + 1
+                )));
+            }
+            let lines = match actions.iter_mut().rev().find_map(|action| match action {
+                PatchAction::Retrieve { lines, .. } | PatchAction::Create { lines, .. } => {
+                    Some(lines)
+                }
+                PatchAction::Message(_) => None,
+            }) {
+                Some(lines) => lines,
+                None => {
+                    if let Err(error) = self.load_str(line) {
+                        return Err(
+                            error.message(&format!("In line {} of the patch file.", index + 1))
+                        );
+                    }
+                    continue;
+                }
+            };
+            lines.push((index, line.to_string()));
+        }
+        Ok(actions)
+    }
+
+    /// A line ending with ` _` continues onto the next line of a file.
+    /// Returns the line without the `_` when it continues.
+    pub fn continuation(line: &str) -> Option<&str> {
+        let head = line.trim_end().strip_suffix('_')?;
+        if !head.ends_with(char::is_whitespace) || head.matches('"').count() % 2 != 0 {
+            return None;
+        }
+        Some(head)
+    }
+
+    /// Join lines continued with ` _` into logical lines. Each is paired
+    /// with the index of the file line it ends on for error messages.
+    pub fn logical_lines(text: &str) -> Vec<(usize, String)> {
+        let mut lines = vec![];
+        let mut logical = String::new();
+        let mut last_index = 0;
+        for (index, line) in text.lines().enumerate() {
+            last_index = index;
+            if let Some(head) = Listing::continuation(line) {
+                logical.push_str(head);
+                continue;
+            }
+            logical.push_str(line);
+            lines.push((index, std::mem::take(&mut logical)));
+        }
+        if !logical.is_empty() {
+            lines.push((last_index, logical));
+        }
+        lines
+    }
+
+    fn patch_filename(filename: &str, index: usize) -> Result<String, Error> {
+        if filename.len() < 3 || !filename.starts_with('"') || !filename.ends_with('"') {
+            return Err(error!(BadFileName; &format!(
+                "In line {} of the patch file.",
+                index + 1
+            )));
+        }
+        Ok(filename[1..filename.len() - 1].to_string())
+    }
+
+    /// Checksum of the listing as used by patch files.
+    pub fn crc32(&self) -> u32 {
+        let mut digest = crc::crc32::Digest::new(crc::crc32::IEEE);
+        for line in self.lines() {
+            digest.write(line.to_string().as_bytes());
+        }
+        digest.sum32()
+    }
+
+    /// Verify a retrieved listing then apply the lines from a patch.
+    /// The `index` of the `Retrieve` action locates a CRC mismatch.
+    pub fn patch(
+        &mut self,
+        crc: u32,
+        index: usize,
+        lines: &[(usize, String)],
+    ) -> Result<(), Error> {
+        let digest = self.crc32();
+        if digest != crc {
+            return Err(error!(SyntaxError; &format!(
+                "Expected CRC {:08X} got {:08X} in line {} of the patch file.",
+                crc, digest, index + 1
+            )));
+        }
+        self.load_patch_lines(lines)
+    }
+
+    /// Load the lines of a patch action, locating any error in the patch file.
+    pub fn load_patch_lines(&mut self, lines: &[(usize, String)]) -> Result<(), Error> {
+        for (index, line) in lines {
+            if let Err(error) = self.load_str(line) {
+                return Err(error.message(&format!("In line {} of the patch file.", index + 1)));
+            }
+        }
+        Ok(())
+    }
+
     pub fn list_line(
         &self,
         range: &mut RangeInclusive<LineNumber>,
@@ -112,7 +294,27 @@ impl Listing {
     }
 
     pub fn renum(&mut self, new_start: u16, old_start: u16, step: u16) -> Result<(), Error> {
-        let mut changes: HashMap<u16, u16> = HashMap::default();
+        let changes: HashMap<u16, u16> = self
+            .renumber_preview(new_start, old_start, step)?
+            .into_iter()
+            .collect();
+        let mut new_source: BTreeMap<LineNumber, Line> = BTreeMap::default();
+        for line in self.lines() {
+            let line = line.renum(&changes);
+            new_source.insert(line.number(), line);
+        }
+        self.source = Arc::from(new_source);
+        Ok(())
+    }
+
+    /// The (old, new) line numbers `renum` would use, without renumbering.
+    pub fn renumber_preview(
+        &self,
+        new_start: u16,
+        old_start: u16,
+        step: u16,
+    ) -> Result<Vec<(u16, u16)>, Error> {
+        let mut changes: Vec<(u16, u16)> = vec![];
         let mut old_end: u16 = LineNumber::max_value() + 1;
         let mut new_num = new_start;
         for (&ln, _) in self.source.iter() {
@@ -127,7 +329,7 @@ impl Listing {
                 if new_num > LineNumber::max_value() {
                     return Err(error!(Overflow));
                 }
-                changes.insert(ln, new_num);
+                changes.push((ln, new_num));
                 new_num = match new_num.checked_add(step) {
                     Some(num) => num,
                     None => return Err(error!(Overflow)),
@@ -136,12 +338,6 @@ impl Listing {
                 old_end = ln;
             }
         }
-        let mut new_source: BTreeMap<LineNumber, Line> = BTreeMap::default();
-        for line in self.lines() {
-            let line = line.renum(&changes);
-            new_source.insert(line.number(), line);
-        }
-        self.source = Arc::from(new_source);
-        Ok(())
+        Ok(changes)
     }
 }
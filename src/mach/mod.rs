@@ -10,7 +10,10 @@ const MAX_LINE_LEN: usize = 1024;
 pub type Address = usize;
 pub type Symbol = isize;
 
+mod bytecode;
+mod clock;
 mod codegen;
+mod filesystem;
 mod function;
 mod link;
 mod listing;
@@ -22,14 +25,25 @@ mod stack;
 mod val;
 mod var;
 
+pub use clock::Clock;
+pub use clock::SystemClock;
+pub use filesystem::FileSystem;
 pub use function::Function;
+pub use function::RndAlgo;
 pub use link::Link;
 pub use listing::Listing;
+pub use listing::PatchAction;
 pub use opcode::Opcode;
 pub use operation::Operation;
+pub use operation::OverflowMode;
 pub use program::Program;
+pub use runtime::EnterResult;
 pub use runtime::Event;
+pub use runtime::ExternFunction;
+pub use runtime::Frame;
+pub use runtime::InputValidator;
 pub use runtime::Runtime;
+pub use runtime::RuntimeSnapshot;
 pub use stack::Stack;
 pub use val::Val;
 pub use var::Var;
@@ -32,6 +32,8 @@ pub enum Opcode {
     Jump(Address),
     /// Process the FOR loop on the stack.
     Next(Rc<str>),
+    /// Drop the innermost FOR loop from the stack and branch to Address.
+    ExitFor(Address),
     /// ON x GOTO/GOSUB lines
     On,
     /// Expect Return(Address) on stack or else error: RETURN WITHOUT GOSUB.
@@ -50,15 +52,20 @@ pub enum Opcode {
     Defstr,
     Delete,
     End,
+    Exit,
+    /// Call a function registered by the host with `Runtime::register_function`.
+    Extern(Rc<str>),
     Fn(Rc<str>),
     Input(Rc<str>),
+    LetDate,
     LetMid,
+    LetTime,
     List,
     Load,
     LoadRun,
     New,
     Print,
-    Read,
+    Read(Rc<str>),
     Renum,
     Restore(Address),
     Save,
@@ -66,6 +73,7 @@ pub enum Opcode {
     Swap,
     Troff,
     Tron,
+    Wait,
 
     // *** Expression operations
     Neg,
@@ -146,6 +154,7 @@ impl std::fmt::Display for Opcode {
             IfNot(a) => write!(f, "IFNOT({})", a),
             Jump(a) => write!(f, "JUMP({})", a),
             Next(a) => write!(f, "NEXT({})", a),
+            ExitFor(a) => write!(f, "EXITFOR({})", a),
             On => write!(f, "ON"),
             Return => write!(f, "RETURN"),
 
@@ -159,15 +168,19 @@ impl std::fmt::Display for Opcode {
             Defstr => write!(f, "DEFSTR"),
             Delete => write!(f, "DELETE"),
             End => write!(f, "END"),
+            Exit => write!(f, "EXIT"),
+            Extern(s) => write!(f, "EXTERN({})", s),
             Fn(s) => write!(f, "FN({})", s),
             Input(s) => write!(f, "INPUT({})", s),
+            LetDate => write!(f, "LETDATE"),
             LetMid => write!(f, "LETMID"),
+            LetTime => write!(f, "LETTIME"),
             List => write!(f, "LIST"),
             Load => write!(f, "LOAD"),
             LoadRun => write!(f, "LOADRUN"),
             New => write!(f, "NEW"),
             Print => write!(f, "PRINT"),
-            Read => write!(f, "READ"),
+            Read(s) => write!(f, "READ({})", s),
             Renum => write!(f, "RENUM"),
             Restore(s) => write!(f, "RESTORE({})", s),
             Save => write!(f, "SAVE"),
@@ -175,6 +188,7 @@ impl std::fmt::Display for Opcode {
             Swap => write!(f, "SWAP"),
             Troff => write!(f, "TROFF"),
             Tron => write!(f, "TRON"),
+            Wait => write!(f, "WAIT"),
 
             Neg => write!(f, "NEG"),
             Pow => write!(f, "POW"),
@@ -1,4 +1,4 @@
-use super::Val;
+use super::{Function, Val};
 use crate::error;
 use crate::lang::Error;
 use std::convert::TryFrom;
@@ -7,7 +7,26 @@ type Result<T> = std::result::Result<T, Error>;
 
 pub struct Operation {}
 
+/// ## Integer overflow behavior
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OverflowMode {
+    /// Integer overflow is an `OVERFLOW` error. The default.
+    #[default]
+    Error,
+    /// Integer overflow wraps around silently like 16-bit hardware.
+    Wrap,
+}
+
 impl Operation {
+    fn integer(mode: OverflowMode, checked: Option<i16>, wrapped: i16) -> Result<Val> {
+        match checked {
+            Some(i) => Ok(Val::Integer(i)),
+            None if mode == OverflowMode::Wrap => Ok(Val::Integer(wrapped)),
+            None => Err(error!(Overflow)),
+        }
+    }
+
     pub fn negate(val: Val) -> Result<Val> {
         use Val::*;
         match val {
@@ -18,14 +37,13 @@ impl Operation {
         }
     }
 
-    pub fn power(lhs: Val, rhs: Val) -> Result<Val> {
+    pub fn power(mode: OverflowMode, lhs: Val, rhs: Val) -> Result<Val> {
         use Val::*;
         match lhs {
             Integer(l) => match rhs {
-                Integer(r) if r >= 0 => match l.checked_pow(r as u32) {
-                    Some(i) => Ok(Integer(i)),
-                    None => Err(error!(Overflow)),
-                },
+                Integer(r) if r >= 0 => {
+                    Operation::integer(mode, l.checked_pow(r as u32), l.wrapping_pow(r as u32))
+                }
                 Integer(r) => Ok(Single((l as f32).powi(r as i32))),
                 Single(r) => Ok(Single((l as f32).powf(r))),
                 Double(r) => Ok(Double((l as f64).powf(r))),
@@ -47,14 +65,11 @@ impl Operation {
         }
     }
 
-    pub fn multiply(lhs: Val, rhs: Val) -> Result<Val> {
+    pub fn multiply(mode: OverflowMode, lhs: Val, rhs: Val) -> Result<Val> {
         use Val::*;
         match lhs {
             Integer(l) => match rhs {
-                Integer(r) => match l.checked_mul(r) {
-                    Some(i) => Ok(Integer(i)),
-                    None => Err(error!(Overflow)),
-                },
+                Integer(r) => Operation::integer(mode, l.checked_mul(r), l.wrapping_mul(r)),
                 Single(r) => Ok(Single(l as f32 * r)),
                 Double(r) => Ok(Double(l as f64 * r)),
                 _ => Err(error!(TypeMismatch)),
@@ -118,7 +133,7 @@ impl Operation {
         }
     }
 
-    pub fn sum(lhs: Val, rhs: Val) -> Result<Val> {
+    pub fn sum(mode: OverflowMode, lhs: Val, rhs: Val) -> Result<Val> {
         use Val::*;
         match lhs {
             String(l) => match rhs {
@@ -126,10 +141,7 @@ impl Operation {
                 _ => Err(error!(TypeMismatch)),
             },
             Integer(l) => match rhs {
-                Integer(r) => match l.checked_add(r) {
-                    Some(i) => Ok(Integer(i)),
-                    None => Err(error!(Overflow)),
-                },
+                Integer(r) => Operation::integer(mode, l.checked_add(r), l.wrapping_add(r)),
                 Single(r) => Ok(Single(l as f32 + r)),
                 Double(r) => Ok(Double(l as f64 + r)),
                 _ => Err(error!(TypeMismatch)),
@@ -150,14 +162,24 @@ impl Operation {
         }
     }
 
-    pub fn subtract(lhs: Val, rhs: Val) -> Result<Val> {
+    pub fn sum_auto_str(mode: OverflowMode, lhs: Val, rhs: Val) -> Result<Val> {
+        use Val::*;
+        match (&lhs, &rhs) {
+            (String(_), Integer(_) | Single(_) | Double(_)) => {
+                Operation::sum(mode, lhs, Function::str(rhs)?)
+            }
+            (Integer(_) | Single(_) | Double(_), String(_)) => {
+                Operation::sum(mode, Function::str(lhs)?, rhs)
+            }
+            _ => Operation::sum(mode, lhs, rhs),
+        }
+    }
+
+    pub fn subtract(mode: OverflowMode, lhs: Val, rhs: Val) -> Result<Val> {
         use Val::*;
         match lhs {
             Integer(l) => match rhs {
-                Integer(r) => match l.checked_sub(r) {
-                    Some(i) => Ok(Integer(i)),
-                    None => Err(error!(Overflow)),
-                },
+                Integer(r) => Operation::integer(mode, l.checked_sub(r), l.wrapping_sub(r)),
                 Single(r) => Ok(Single(l as f32 - r)),
                 Double(r) => Ok(Double(l as f64 - r)),
                 _ => Err(error!(TypeMismatch)),
@@ -178,49 +200,51 @@ impl Operation {
         }
     }
 
-    pub fn equal(lhs: Val, rhs: Val) -> Result<Val> {
-        if Operation::equal_bool(lhs, rhs)? {
+    pub fn equal(epsilon: Option<f64>, lhs: Val, rhs: Val) -> Result<Val> {
+        if Operation::equal_bool(epsilon, lhs, rhs)? {
             Ok(Val::Integer(-1))
         } else {
             Ok(Val::Integer(0))
         }
     }
 
-    pub fn not_equal(lhs: Val, rhs: Val) -> Result<Val> {
-        if Operation::equal_bool(lhs, rhs)? {
+    pub fn not_equal(epsilon: Option<f64>, lhs: Val, rhs: Val) -> Result<Val> {
+        if Operation::equal_bool(epsilon, lhs, rhs)? {
             Ok(Val::Integer(0))
         } else {
             Ok(Val::Integer(-1))
         }
     }
 
-    fn equal_bool(lhs: Val, rhs: Val) -> Result<bool> {
+    /// Floats are equal within `epsilon`, or the machine epsilon of the type if `None`.
+    fn equal_bool(epsilon: Option<f64>, lhs: Val, rhs: Val) -> Result<bool> {
         use Val::*;
-        match lhs {
+        let (diff, type_epsilon) = match lhs {
             Integer(l) => match rhs {
-                Integer(r) => Ok(l == r),
-                Single(r) => Ok((l as f32 - r).abs() <= std::f32::EPSILON),
-                Double(r) => Ok((l as f64 - r).abs() <= std::f64::EPSILON),
-                _ => Err(error!(TypeMismatch)),
+                Integer(r) => return Ok(l == r),
+                Single(r) => ((l as f32 - r).abs() as f64, f32::EPSILON as f64),
+                Double(r) => ((l as f64 - r).abs(), f64::EPSILON),
+                _ => return Err(error!(TypeMismatch)),
             },
             Single(l) => match rhs {
-                Integer(r) => Ok((l - r as f32).abs() <= std::f32::EPSILON),
-                Single(r) => Ok((l - r).abs() <= std::f32::EPSILON),
-                Double(r) => Ok((l as f64 - r).abs() <= std::f64::EPSILON),
-                _ => Err(error!(TypeMismatch)),
+                Integer(r) => ((l - r as f32).abs() as f64, f32::EPSILON as f64),
+                Single(r) => ((l - r).abs() as f64, f32::EPSILON as f64),
+                Double(r) => ((l as f64 - r).abs(), f64::EPSILON),
+                _ => return Err(error!(TypeMismatch)),
             },
             Double(l) => match rhs {
-                Integer(r) => Ok((l - r as f64).abs() <= std::f64::EPSILON),
-                Single(r) => Ok((l - r as f64).abs() <= std::f64::EPSILON),
-                Double(r) => Ok((l - r).abs() <= std::f64::EPSILON),
-                _ => Err(error!(TypeMismatch)),
+                Integer(r) => ((l - r as f64).abs(), f64::EPSILON),
+                Single(r) => ((l - r as f64).abs(), f64::EPSILON),
+                Double(r) => ((l - r).abs(), f64::EPSILON),
+                _ => return Err(error!(TypeMismatch)),
             },
             String(l) => match rhs {
-                String(r) => Ok(l == r),
-                _ => Err(error!(TypeMismatch)),
+                String(r) => return Ok(l == r),
+                _ => return Err(error!(TypeMismatch)),
             },
-            Return(_) | Next(_) => Err(error!(TypeMismatch)),
-        }
+            Return(_) | Next(_) => return Err(error!(TypeMismatch)),
+        };
+        Ok(diff <= epsilon.unwrap_or(type_epsilon))
     }
 
     pub fn greater(lhs: Val, rhs: Val) -> Result<Val> {
@@ -1,5 +1,10 @@
+use super::bytecode::{Reader, Writer};
 use super::{codegen::codegen, Address, Link, Opcode, Symbol, Val};
-use crate::lang::{Error, Line, LineNumber};
+use crate::error;
+use crate::lang::{Column, Error, Line, LineNumber};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
 use std::sync::Arc;
 
 type Result<T> = std::result::Result<T, Error>;
@@ -13,6 +18,7 @@ pub struct Program {
     direct_address: Address,
     line_number: LineNumber,
     link: Link,
+    externs: HashMap<Rc<str>, RangeInclusive<usize>>,
 }
 
 impl Program {
@@ -24,11 +30,28 @@ impl Program {
         self.link.append(link)
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.link.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.link.len()
+    }
+
+    /// Opcodes ahead of any direct mode line, once linked.
+    pub fn indirect_len(&self) -> usize {
+        self.direct_address
+    }
+
+    pub fn data_len(&self) -> usize {
+        self.link.data_len()
+    }
+
     pub fn get(&self, addr: Address) -> Option<Opcode> {
         self.link.get(addr).cloned()
     }
 
-    pub fn read_data(&mut self) -> Result<Val> {
+    pub fn read_data(&mut self) -> Result<(Val, Rc<str>, LineNumber)> {
         self.link.read_data()
     }
 
@@ -36,10 +59,32 @@ impl Program {
         self.link.restore_data(addr)
     }
 
+    pub fn data_pos(&self) -> Address {
+        self.link.data_pos()
+    }
+
     pub fn line_number_for(&self, op_addr: Address) -> LineNumber {
         self.link.line_number_for(op_addr)
     }
 
+    /// Dangling line references such as `GOTO` to a missing line.
+    pub fn undefined_lines(&self) -> Vec<(u16, u16)> {
+        self.link.undefined_lines()
+    }
+
+    pub fn column_for(&self, op_addr: Address) -> Option<Column> {
+        self.link.column_for(op_addr)
+    }
+
+    /// Compile calls to `name` as `Opcode::Extern`. Kept by `clear()`.
+    pub fn set_extern(&mut self, name: Rc<str>, arity: RangeInclusive<usize>) {
+        self.externs.insert(name, arity);
+    }
+
+    pub fn extern_arity(&self, name: &str) -> Option<RangeInclusive<usize>> {
+        self.externs.get(name).cloned()
+    }
+
     pub fn clear(&mut self) {
         self.errors = Arc::default();
         self.indirect_errors = Arc::default();
@@ -48,6 +93,28 @@ impl Program {
         self.link.clear();
     }
 
+    /// Compiled program lines as bytecode. Only lines already linked
+    /// are included and errors are not saved, so link first and check.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer::default();
+        self.link.serialize(&mut w, self.direct_address);
+        w.finish()
+    }
+
+    /// Restore a program from `serialize`, ready for a direct line.
+    pub fn deserialize(bytes: &[u8]) -> Result<Program> {
+        let mut r = Reader::new(bytes)?;
+        let link = Link::deserialize(&mut r)?;
+        if !r.is_empty() {
+            return Err(error!(SyntaxError; "INVALID BYTECODE"));
+        }
+        Ok(Program {
+            direct_address: link.len(),
+            link,
+            ..Program::default()
+        })
+    }
+
     pub fn codegen<'b, T: IntoIterator<Item = &'b Line>>(&mut self, lines: T) {
         let mut direct_seen = false;
         for line in lines {
@@ -78,7 +145,7 @@ impl Program {
                     continue;
                 }
             };
-            codegen(self, &ast);
+            codegen(self, &ast, line);
             if self.line_number.is_none() {
                 if let Err(e) = self.link.push(Opcode::End) {
                     Arc::make_mut(&mut self.errors).push(e);
@@ -1,7 +1,9 @@
+extern crate chrono;
 extern crate rand;
 use super::*;
 use crate::error;
-use crate::lang::{Error, Line, LineNumber, MaxValue};
+use crate::lang::{lex, token::Token, Error, Line, LineNumber, MaxValue};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::ops::{Range, RangeInclusive};
@@ -12,26 +14,49 @@ type Result<T> = std::result::Result<T, Error>;
 
 const INTRO: &str = "64K BASIC";
 const PROMPT: &str = "READY.";
+const MAX_UNDO: usize = 50;
+const TAB_WIDTH: usize = 8;
 
 /// ## Virtual machine
-
 pub struct Runtime {
     prompt: String,
+    banner: Option<String>,
     listing: Listing,
+    filesystem: Option<Box<dyn FileSystem>>,
+    input_validator: Option<InputValidator>,
+    clock: Box<dyn Clock>,
+    clock_offset: Duration,
+    undo: Vec<Listing>,
+    redo: Vec<Listing>,
     dirty: bool,
     program: Program,
     pc: Address,
+    last_pc: Option<Address>,
+    cycles: usize,
     tr: LineNumber,
+    stop_at: Option<u16>,
     tron: bool,
+    auto_print: bool,
     entry_address: Address,
     stack: RuntimeStack,
     vars: Var,
+    presets: Vec<(Rc<str>, Val)>,
     state: State,
     cont: State,
     cont_pc: Address,
     print_col: usize,
+    tab_width: usize,
+    print_buf: String,
+    input_redo: String,
+    input_col: Option<usize>,
+    pending: Option<Event>,
     rand: (u32, u32, u32),
+    rnd_algo: RndAlgo,
+    overflow: OverflowMode,
+    epsilon: Option<f64>,
+    auto_str: bool,
     functions: HashMap<Rc<str>, (usize, Address)>,
+    externs: HashMap<Rc<str>, (RangeInclusive<usize>, ExternFunction)>,
 }
 
 /// ## Events for the user interface
@@ -39,7 +64,9 @@ pub struct Runtime {
 #[derive(Debug)]
 pub enum Event {
     Errors(Arc<Vec<Error>>),
-    Input(String, bool),
+    /// Prompt, caps lock, the rejected entry to prefill after `?REDO FROM START`,
+    /// and false when the host must not echo a newline after the entry.
+    Input(String, bool, String, bool),
     Print(String),
     List((String, Vec<Range<usize>>)),
     Running,
@@ -47,8 +74,57 @@ pub enum Event {
     Load(String),
     Run(String),
     Save(String),
-    Cls,
+    /// `CLS` mode: 0 clears the screen, 1 graphics, 2 the text window.
+    Cls(u8),
     Inkey,
+    /// `END` with an exit code for the host process.
+    Exit(i32),
+}
+
+/// ## What became of an entered line
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnterResult {
+    /// A direct mode statement will run.
+    Ran,
+    /// A numbered line was added, replaced, or removed.
+    Edited,
+    /// Nothing to do, such as a blank line or an `INKEY$` key.
+    Ignored,
+    /// Answer to an `INPUT` statement.
+    AwaitingInput,
+}
+
+/// ## Active `FOR` loop or `GOSUB` for a debugger view
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// Loop variable, limit, step, and the line of the `FOR` statement.
+    For {
+        var_name: Rc<str>,
+        to: Val,
+        step: Val,
+        line_number: LineNumber,
+    },
+    /// Line of the `GOSUB` or `FN` call that will be returned to.
+    Gosub { line_number: LineNumber },
+}
+
+/// ## Saved execution state
+///
+/// Variables, stack, and the `CONT` position. The program itself is not
+/// included and must be unchanged for `CONT` to resume after a restore.
+
+#[derive(Clone)]
+pub struct RuntimeSnapshot {
+    vars: Var,
+    stack: RuntimeStack,
+    cont: bool,
+    cont_pc: Address,
+    rand: (u32, u32, u32),
+    functions: HashMap<Rc<str>, (usize, Address)>,
+    data_pos: Address,
+    print_col: usize,
 }
 
 #[derive(Debug)]
@@ -59,7 +135,7 @@ enum State {
     RuntimeError(Error),
     Running,
     Input,
-    InputRedo,
+    InputRedo(Option<String>),
     InputRunning,
     Interrupt,
     Inkey,
@@ -69,21 +145,43 @@ impl Default for Runtime {
     fn default() -> Self {
         Runtime {
             prompt: PROMPT.into(),
+            banner: None,
             listing: Listing::default(),
+            filesystem: None,
+            input_validator: None,
+            clock: Box::new(SystemClock),
+            clock_offset: Duration::zero(),
+            undo: vec![],
+            redo: vec![],
             dirty: false,
             program: Program::default(),
             pc: 0,
+            last_pc: None,
+            cycles: 0,
             tr: None,
+            stop_at: None,
             tron: false,
+            auto_print: false,
             entry_address: 1,
             stack: Stack::new("STACK OVERFLOW"),
             vars: Var::new(),
+            presets: vec![],
             state: State::Intro,
             cont: State::Stopped,
             cont_pc: 0,
             print_col: 0,
+            tab_width: TAB_WIDTH,
+            print_buf: String::new(),
+            input_redo: String::new(),
+            input_col: None,
+            pending: None,
             rand: (1, 1, 1),
+            rnd_algo: RndAlgo::default(),
+            overflow: OverflowMode::default(),
+            epsilon: None,
+            auto_str: false,
             functions: HashMap::default(),
+            externs: HashMap::default(),
         }
     }
 }
@@ -92,40 +190,60 @@ impl Runtime {
     /// Enters a line of BASIC or INPUT.
     /// Returns true if good candidate for history.
     pub fn enter(&mut self, string: &str) -> bool {
+        matches!(
+            self.enter_ex(string),
+            EnterResult::Ran | EnterResult::AwaitingInput
+        )
+    }
+
+    /// Enters a line of BASIC or INPUT and reports what was done with it.
+    pub fn enter_ex(&mut self, string: &str) -> EnterResult {
         if let State::Input = self.state {
             self.enter_input(string);
-            self.print_col = 0;
-            return true;
+            self.print_col = match self.input_col {
+                Some(col) => col + string.chars().count(),
+                None => 0,
+            };
+            return EnterResult::AwaitingInput;
         }
         if let State::Inkey = self.state {
             self.enter_inkey(string);
-            return false;
+            return EnterResult::Ignored;
         }
         debug_assert!(matches!(self.state, State::Stopped | State::Intro));
         if string.len() > MAX_LINE_LEN {
             self.state = State::RuntimeError(error!(LineBufferOverflow));
-            return false;
+            return EnterResult::Ignored;
+        }
+        let mut line = Line::new(string);
+        // `1+2` lexes as line 1 but is taken as an expression since the
+        // number touches an operator. `20 +5` is still a program line.
+        let expression = line.is_direct()
+            || string
+                .trim_start()
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .starts_with(['+', '-', '*', '/', '\\', '^', '=', '<', '>']);
+        if self.auto_print && expression && line.ast().is_err() {
+            let print_line = Line::new(&format!("PRINT {}", string));
+            if print_line.ast().is_ok() {
+                line = print_line;
+            }
         }
-        let line = Line::new(string);
         if line.is_direct() {
             if line.is_empty() {
-                false
+                EnterResult::Ignored
             } else {
                 self.enter_direct(line);
-                true
+                EnterResult::Ran
             }
         } else {
             self.enter_indirect(line);
-            false
+            EnterResult::Edited
         }
     }
 
     fn enter_direct(&mut self, line: Line) {
-        if self.dirty {
-            self.program.clear();
-            self.program.codegen(self.listing.lines());
-            self.dirty = false;
-        }
+        self.compile();
         self.program.codegen(&line);
         let (pc, indirect_errors, direct_errors) = self.program.link();
         self.pc = pc;
@@ -136,14 +254,36 @@ impl Runtime {
         self.state = State::Running;
     }
 
+    fn compile(&mut self) {
+        if self.dirty {
+            self.program.clear();
+            self.program.codegen(self.listing.lines());
+            self.program.link();
+            self.dirty = false;
+        }
+    }
+
     fn enter_indirect(&mut self, line: Line) {
         self.cont = State::Stopped;
+        let snapshot = self.listing.clone();
         if line.is_empty() {
             self.dirty = self.listing.remove(line.number()).is_some();
         } else {
             self.listing.insert(line);
             self.dirty = true;
         }
+        if self.dirty {
+            self.push_undo(snapshot);
+        }
+    }
+
+    /// Save the listing from before an edit so `undo()` can return to it.
+    fn push_undo(&mut self, snapshot: Listing) {
+        if self.undo.len() == MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(snapshot);
+        self.redo.clear();
     }
 
     fn enter_inkey(&mut self, mut string: &str) {
@@ -159,9 +299,11 @@ impl Runtime {
 
     fn enter_input(&mut self, string: &str) {
         if string.len() > MAX_LINE_LEN {
-            self.state = State::InputRedo;
+            self.input_redo.clear();
+            self.state = State::InputRedo(None);
             return;
         }
+        self.input_redo = string.to_string();
         if let Err(error) = self.do_input(string) {
             self.clear();
             self.state = State::RuntimeError(error);
@@ -174,6 +316,20 @@ impl Runtime {
             Some(Val::Integer(n)) => *n as usize,
             _ => return Err(error!(InternalError)),
         };
+        let validated;
+        let string = match &self.input_validator {
+            Some(validator) => match validator(string) {
+                Ok(s) => {
+                    validated = s;
+                    validated.as_str()
+                }
+                Err(message) => {
+                    self.state = State::InputRedo(Some(message));
+                    return Ok(());
+                }
+            },
+            None => string,
+        };
         let mut vec_val: Vec<Val> = vec![];
         if len <= 1 {
             vec_val.push(Val::String(string.into()));
@@ -194,7 +350,7 @@ impl Runtime {
             }
             vec_val.push(Val::String(string[start..].into()));
             if len != vec_val.len() {
-                self.state = State::InputRedo;
+                self.state = State::InputRedo(None);
                 return Ok(());
             }
         }
@@ -230,20 +386,336 @@ impl Runtime {
 
     /// Set a new listing. Used to load a program.
     pub fn set_listing(&mut self, listing: Listing, run: bool) {
-        self.r#new_();
+        let snapshot = self.listing.clone();
+        self.new_program();
         self.listing = listing;
+        self.push_undo(snapshot);
         if run {
             self.enter("RUN");
         }
     }
 
+    /// Install a compiled program such as one from `Program::deserialize`.
+    /// The listing is cleared; editing a line discards the program.
+    pub fn set_program(&mut self, program: Program) {
+        self.r#new_();
+        self.program = program;
+        for (name, (arity, _)) in &self.externs {
+            self.program.set_extern(name.clone(), arity.clone());
+        }
+        self.dirty = false;
+    }
+
+    /// Add a built-in function such as `CUBE(X)` implemented by the host.
+    /// Calls with a number of arguments outside `arity` will not compile.
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        arity: RangeInclusive<usize>,
+        function: ExternFunction,
+    ) -> Result<()> {
+        let name: Rc<str> = match lex(name).1.as_slice() {
+            [Token::Ident(ident)] => ident.to_string().into(),
+            _ => return Err(error!(SyntaxError; "EXPECTED FUNCTION NAME")),
+        };
+        if name.starts_with("FN") || Function::opcode_and_arity(&name).is_some() {
+            return Err(error!(SyntaxError; "RESERVED FOR BUILT-IN"));
+        }
+        self.program.set_extern(name.clone(), arity.clone());
+        self.externs.insert(name, (arity, function));
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Insert, replace, or delete one program line. Variables are kept
+    /// so a stopped program can resume with `GOTO`.
+    pub fn replace_line(&mut self, src: &str) -> Result<()> {
+        if src.len() > MAX_LINE_LEN {
+            return Err(error!(LineBufferOverflow));
+        }
+        let line = Line::new(src);
+        if line.is_direct() {
+            return Err(error!(IllegalDirect));
+        }
+        self.enter_indirect(line);
+        Ok(())
+    }
+
+    /// Load a program from source text. Nothing is loaded if any line has an error.
+    pub fn load_program(&mut self, src: &str) -> std::result::Result<(), Vec<Error>> {
+        let listing = Runtime::parse_program(src)?;
+        self.set_listing(listing, false);
+        Ok(())
+    }
+
+    fn parse_program(src: &str) -> std::result::Result<Listing, Vec<Error>> {
+        let mut listing = Listing::default();
+        let mut errors: Vec<Error> = vec![];
+        for (index, line) in Listing::logical_lines(src) {
+            if let Err(error) = listing.load_str(&line) {
+                errors.push(error.message(&format!("In line {} of the file.", index + 1)));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(listing)
+    }
+
+    /// Resolve `LOAD`, `SAVE`, and `RUN "<filename>"` with a virtual
+    /// file system instead of `Event::Load`, `Event::Save`, and `Event::Run`.
+    pub fn set_filesystem(&mut self, filesystem: Option<Box<dyn FileSystem>>) {
+        self.filesystem = filesystem;
+    }
+
+    /// Check or rewrite each `INPUT` entry before it is assigned.
+    /// An error asks for the entry again with the message shown.
+    pub fn set_input_validator(&mut self, validator: Option<InputValidator>) {
+        self.input_validator = validator;
+    }
+
+    /// Replace the system clock used by `TIME$` and `DATE$`.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+        self.clock_offset = Duration::zero();
+    }
+
+    fn now(&self) -> NaiveDateTime {
+        self.clock.now() + self.clock_offset
+    }
+
+    fn read_program(&self, filename: &str) -> Result<Option<Listing>> {
+        match &self.filesystem {
+            Some(filesystem) => match Runtime::parse_program(&filesystem.read(filename)?) {
+                Ok(listing) => Ok(Some(listing)),
+                Err(mut errors) => Err(errors.remove(0)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Revert the most recent program line edit.
+    /// Returns false if there is nothing to undo or a program is running.
+    pub fn undo(&mut self) -> bool {
+        if !self.is_editable() {
+            return false;
+        }
+        match self.undo.pop() {
+            Some(listing) => {
+                self.redo
+                    .push(std::mem::replace(&mut self.listing, listing));
+                self.restore_listing();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapply the most recently undone program line edit.
+    /// Returns false if there is nothing to redo or a program is running.
+    pub fn redo(&mut self) -> bool {
+        if !self.is_editable() {
+            return false;
+        }
+        match self.redo.pop() {
+            Some(listing) => {
+                self.undo
+                    .push(std::mem::replace(&mut self.listing, listing));
+                self.restore_listing();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn is_editable(&self) -> bool {
+        matches!(self.state, State::Stopped | State::Intro)
+    }
+
+    fn restore_listing(&mut self) {
+        self.cont = State::Stopped;
+        self.dirty = true;
+    }
+
+    /// Number of lines in the program.
+    pub fn line_count(&self) -> usize {
+        self.listing.lines().count()
+    }
+
+    /// Compiled size of the program as opcodes and DATA items.
+    pub fn program_size(&mut self) -> (usize, usize) {
+        self.compile();
+        (self.program.indirect_len(), self.program.data_len())
+    }
+
+    /// Number of elements in a dimensioned array such as `A$`.
+    pub fn array_len(&self, var_name: &str) -> Option<usize> {
+        self.vars.array_len(var_name)
+    }
+
+    /// True if a variable such as `A$` was ever assigned, even if only to zero.
+    pub fn is_set(&self, var_name: &str) -> bool {
+        self.vars.is_set(var_name)
+    }
+
+    /// Remove one scalar variable such as `A$` so it reads as unset.
+    /// A value from `set_variable` is also forgotten.
+    pub fn clear_variable(&mut self, var_name: &str) {
+        let var_name: Rc<str> = var_name.into();
+        self.presets.retain(|(name, _)| *name != var_name);
+        self.vars.clear_scalar(&var_name)
+    }
+
+    /// Seed a scalar variable such as `N%` for the program. The value is
+    /// stored again whenever variables are cleared, as by `RUN` or `CLEAR`.
+    pub fn set_variable(&mut self, var_name: &str, value: Val) -> Result<()> {
+        let var_name: Rc<str> = match lex(var_name).1.as_slice() {
+            [Token::Ident(ident)] => ident.to_string().into(),
+            _ => return Err(error!(SyntaxError; "EXPECTED VARIABLE")),
+        };
+        self.vars.store(&var_name, value)?;
+        let value = self.vars.fetch(&var_name);
+        self.presets.retain(|(name, _)| *name != var_name);
+        self.presets.push((var_name, value));
+        Ok(())
+    }
+
+    /// Save variables and the stopped position, such as after `run_to_line`.
+    pub fn snapshot(&self) -> RuntimeSnapshot {
+        RuntimeSnapshot {
+            vars: self.vars.clone(),
+            stack: self.stack.clone(),
+            cont: !matches!(self.cont, State::Stopped),
+            cont_pc: self.cont_pc,
+            rand: self.rand,
+            functions: self.functions.clone(),
+            data_pos: self.program.data_pos(),
+            print_col: self.print_col,
+        }
+    }
+
+    /// Return to a snapshot. `CONT` resumes from where it was taken.
+    pub fn restore_snapshot(&mut self, snapshot: RuntimeSnapshot) {
+        self.vars = snapshot.vars;
+        self.stack = snapshot.stack;
+        self.cont = if snapshot.cont && !self.dirty {
+            State::Running
+        } else {
+            State::Stopped
+        };
+        self.cont_pc = snapshot.cont_pc;
+        self.rand = snapshot.rand;
+        self.functions = snapshot.functions;
+        self.program.restore_data(snapshot.data_pos);
+        self.print_col = snapshot.print_col;
+    }
+
+    /// Active loops and subroutines, outermost first.
+    pub fn call_stack(&self) -> Vec<Frame> {
+        let mut frames = vec![];
+        for idx in 0..self.stack.len() {
+            match self.stack.get(idx) {
+                Some(Val::Next(addr)) if idx >= 3 => {
+                    if let (Some(Val::String(var_name)), Some(step), Some(to)) = (
+                        self.stack.get(idx - 1),
+                        self.stack.get(idx - 2),
+                        self.stack.get(idx - 3),
+                    ) {
+                        frames.push(Frame::For {
+                            var_name: var_name.clone(),
+                            to: to.clone(),
+                            step: step.clone(),
+                            line_number: self.program.line_number_for(addr.saturating_sub(1)),
+                        });
+                    }
+                }
+                Some(Val::Return(addr)) => frames.push(Frame::Gosub {
+                    line_number: self.program.line_number_for(addr.saturating_sub(1)),
+                }),
+                _ => {}
+            }
+        }
+        frames
+    }
+
     /// Set a prompt instead of the default "READY."
     pub fn set_prompt(&mut self, prompt: &str) {
         self.prompt = prompt.into();
     }
 
+    /// Replace the intro banner. `None` restores the default
+    /// and an empty string suppresses it.
+    pub fn set_banner(&mut self, banner: Option<String>) {
+        self.banner = banner;
+    }
+
+    /// True when `TRON` is in effect.
+    pub fn is_tracing(&self) -> bool {
+        self.tron
+    }
+
+    /// Select the generator used by `RND`.
+    pub fn set_rnd_algorithm(&mut self, algo: RndAlgo) {
+        self.rnd_algo = algo;
+    }
+
+    /// Print direct entries such as `1+2` which are only valid as an expression.
+    /// Numbered program lines such as `20 +5` are stored as usual.
+    pub fn set_auto_print(&mut self, auto_print: bool) {
+        self.auto_print = auto_print;
+    }
+
+    /// Set the tab stop width used to track the column after `CHR$(9)`.
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.max(1);
+    }
+
+    /// Select whether Integer arithmetic errors or wraps on overflow.
+    pub fn set_integer_overflow(&mut self, mode: OverflowMode) {
+        self.overflow = mode;
+    }
+
+    /// Treat floats as equal when they differ by no more than `epsilon`.
+    /// By default only the machine epsilon of the type is allowed.
+    pub fn set_float_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = Some(epsilon);
+    }
+
+    /// Let `+` convert a number joined to a string as if by `STR$`.
+    pub fn set_auto_str(&mut self, auto_str: bool) {
+        self.auto_str = auto_str;
+    }
+
+    /// Keep variables assigned zero or empty in memory. By default they
+    /// are removed since an unset variable reads the same.
+    pub fn set_retain_zero(&mut self, retain_zero: bool) {
+        self.vars.set_retain_zero(retain_zero);
+    }
+
+    /// Number of variable values in memory, including array elements.
+    pub fn variable_count(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// Upper bound for arrays used without `DIM`. The default is 10.
+    pub fn set_default_dim(&mut self, dim: usize) {
+        self.vars.set_default_dim(dim);
+    }
+
+    /// Most values the runtime stack may hold for expressions, `FOR`
+    /// loops, and `GOSUB`. The default is 65535.
+    pub fn set_value_stack_size(&mut self, size: usize) {
+        self.stack.set_capacity(size);
+    }
+
+    /// Require arrays to be dimensioned with `DIM` before use.
+    pub fn set_strict_arrays(&mut self, strict: bool) {
+        self.vars.set_strict(strict);
+    }
+
     /// Interrupt the program. Displays `BREAK` error.
     pub fn interrupt(&mut self) {
+        self.pending = None;
         self.cont = State::Interrupt;
         std::mem::swap(&mut self.state, &mut self.cont);
         self.cont_pc = self.pc;
@@ -253,6 +725,45 @@ impl Runtime {
         }
     }
 
+    /// Cancel a pending INPUT. Displays `BREAK` error like an interrupt.
+    /// Returns false if no INPUT was pending.
+    pub fn cancel_input(&mut self) -> bool {
+        if matches!(self.state, State::Input | State::InputRedo(_)) {
+            self.input_redo.clear();
+            self.interrupt();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Same as execute but also returns the number of opcodes executed.
+    pub fn execute_counted(&mut self, iterations: usize) -> (Event, usize) {
+        let event = self.execute(iterations);
+        (event, self.cycles)
+    }
+
+    /// Same as execute but bounded by wall time instead of opcodes.
+    /// Returns `Event::Running` once the budget is spent.
+    pub fn execute_for(&mut self, budget: std::time::Duration) -> Event {
+        let start = std::time::Instant::now();
+        loop {
+            let event = self.execute(1000);
+            if !matches!(event, Event::Running) || start.elapsed() >= budget {
+                return event;
+            }
+        }
+    }
+
+    /// Same as execute but stops with `CONT` available when
+    /// execution is about to enter the given line.
+    pub fn run_to_line(&mut self, line: u16, max_cycles: usize) -> Event {
+        self.stop_at = Some(line);
+        let event = self.execute(max_cycles);
+        self.stop_at = None;
+        event
+    }
+
     /// Use a large number for iterations but not so much
     /// that interrupts aren't responsive.
     pub fn execute(&mut self, iterations: usize) -> Event {
@@ -261,16 +772,32 @@ impl Runtime {
             pc = pc.saturating_sub(1);
             this.program.line_number_for(pc)
         }
+        self.cycles = 0;
+        if let Some(event) = self.pending.take() {
+            return event;
+        }
         match &self.state {
             State::Intro => {
                 self.state = State::Stopped;
-                let mut s = INTRO.to_string();
-                if let Some(version) = option_env!("CARGO_PKG_VERSION") {
-                    s.push(' ');
-                    s.push_str(version);
+                let mut s = match &self.banner {
+                    Some(banner) => banner.clone(),
+                    None => {
+                        let mut s = INTRO.to_string();
+                        if let Some(version) = option_env!("CARGO_PKG_VERSION") {
+                            s.push(' ');
+                            s.push_str(version);
+                        }
+                        #[cfg(debug_assertions)]
+                        s.push_str("+debug");
+                        s
+                    }
+                };
+                if s.is_empty() {
+                    return match self.ready_prompt() {
+                        Some(e) => e,
+                        None => Event::Stopped,
+                    };
                 }
-                #[cfg(debug_assertions)]
-                s.push_str("+debug");
                 s.push('\n');
                 return Event::Print(s);
             }
@@ -295,9 +822,13 @@ impl Runtime {
                     self.state = State::RuntimeError(error.in_line_number(line_number(self)))
                 }
             },
-            State::InputRedo => {
+            State::InputRedo(message) => {
+                let error = match message {
+                    Some(message) => error!(RedoFromStart; message),
+                    None => error!(RedoFromStart),
+                };
                 self.state = State::Input;
-                return Event::Errors(Arc::new(vec![error!(RedoFromStart)]));
+                return Event::Errors(Arc::new(vec![error]));
             }
             State::InputRunning | State::Running => {
                 if !self.listing.direct_errors.is_empty() {
@@ -319,12 +850,12 @@ impl Runtime {
             }
         }
         debug_assert!(matches!(self.state, State::Running | State::InputRunning));
-        match self.execute_loop(iterations) {
+        let event = match self.execute_loop(iterations) {
             Ok(event) => {
                 if let State::Stopped = self.state {
                     if let Event::Stopped = event {
                         if let Some(event) = self.ready_prompt() {
-                            return event;
+                            return self.flush_print(event);
                         }
                     }
                 }
@@ -342,9 +873,17 @@ impl Runtime {
                             _ => continue,
                         }
                     }
-                    self.state = State::InputRedo;
+                    self.state = State::InputRedo(None);
                 } else {
-                    self.cont = State::RuntimeError(error.in_line_number(line_number(self)));
+                    let error = match self.program.column_for(self.pc.wrapping_sub(1)) {
+                        Some(col) if error.column() == (0..0) => error.in_column(&col),
+                        _ => error,
+                    };
+                    let error = match error.line_number() {
+                        Some(_) => error,
+                        None => error.in_line_number(line_number(self)),
+                    };
+                    self.cont = State::RuntimeError(error);
                     std::mem::swap(&mut self.cont, &mut self.state);
                     self.cont_pc = self.pc;
                     if self.pc >= self.entry_address || self.stack.is_full() {
@@ -354,7 +893,23 @@ impl Runtime {
                 }
                 Event::Running
             }
+        };
+        self.flush_print(event)
+    }
+
+    /// PRINT output is buffered in execute_loop. The buffer goes out
+    /// before the event that ended the loop, which may have to wait.
+    fn flush_print(&mut self, event: Event) -> Event {
+        if self.print_buf.is_empty() {
+            return event;
+        }
+        let mut s = std::mem::take(&mut self.print_buf);
+        match event {
+            Event::Print(ps) => s.push_str(&ps),
+            Event::Running => {}
+            _ => self.pending = Some(event),
         }
+        Event::Print(s)
     }
 
     fn execute_input(&mut self) -> Result<Event> {
@@ -366,16 +921,46 @@ impl Runtime {
         };
         prompt.push('?');
         prompt.push(' ');
-        let is_caps = !matches!(caps, Val::Integer(i) if i == 0);
+        let flags = i16::try_from(caps.clone())?;
+        let is_caps = flags & 1 != 0;
+        let is_newline = flags & 2 == 0;
+        self.input_col = if is_newline {
+            None
+        } else {
+            Some(prompt.chars().count())
+        };
         self.stack.push(caps)?;
         self.stack.push(len)?;
         self.print_col = 0;
-        Ok(Event::Input(prompt, is_caps))
+        Ok(Event::Input(
+            prompt,
+            is_caps,
+            self.input_redo.clone(),
+            is_newline,
+        ))
     }
 
     fn execute_loop(&mut self, iterations: usize) -> Result<Event> {
         let has_indirect_errors = !self.listing.indirect_errors.is_empty();
+        let overflow = self.overflow;
+        let epsilon = self.epsilon;
+        let auto_str = self.auto_str;
         for _ in 0..iterations {
+            if let (Some(line), Some(last_pc)) = (self.stop_at, self.last_pc) {
+                if self.pc < self.entry_address
+                    && self.program.line_number_for(self.pc) == Some(line)
+                {
+                    let line_start = match self.pc.checked_sub(1) {
+                        Some(pc) => self.program.line_number_for(pc) != Some(line),
+                        None => true,
+                    };
+                    if self.program.line_number_for(last_pc) != Some(line)
+                        || (line_start && self.pc <= last_pc)
+                    {
+                        return Ok(self.r#end());
+                    }
+                }
+            }
             if self.tron {
                 let tr = self.program.line_number_for(self.pc);
                 if tr != self.tr {
@@ -391,7 +976,9 @@ impl Runtime {
                 Some(v) => v,
                 None => return Err(error!(InternalError; "INVALID PC ADDRESS")),
             };
+            self.last_pc = Some(self.pc);
             self.pc += 1;
+            self.cycles += 1;
             match op {
                 Opcode::Literal(val) => self.stack.push(val.clone())?,
                 Opcode::Pop(var_name) => self.vars.store(&var_name, self.stack.pop()?)?,
@@ -445,21 +1032,26 @@ impl Runtime {
                 Opcode::Defstr => self.r#defstr()?,
                 Opcode::Delete => return self.r#delete(),
                 Opcode::End => return Ok(self.r#end()),
+                Opcode::Exit => return self.r#exit(),
+                Opcode::ExitFor(addr) => self.r#exit_for(addr)?,
+                Opcode::Extern(var_name) => self.r#extern(var_name)?,
                 Opcode::Fn(var_name) => self.r#fn(var_name)?,
                 Opcode::Input(var_name) => {
                     if let Some(event) = self.r#input(var_name)? {
                         return Ok(event);
                     }
                 }
+                Opcode::LetDate => self.r#letdate()?,
                 Opcode::LetMid => self.r#letmid()?,
+                Opcode::LetTime => self.r#lettime()?,
                 Opcode::List => return self.r#list(),
                 Opcode::Load => return self.r#load(),
                 Opcode::LoadRun => return self.r#loadrun(),
                 Opcode::New => return Ok(self.r#new_()),
                 Opcode::On => self.r#on()?,
                 Opcode::Next(var_name) => self.r#next(var_name)?,
-                Opcode::Print => return self.r#print(),
-                Opcode::Read => self.r#read()?,
+                Opcode::Print => self.r#print()?,
+                Opcode::Read(var_name) => self.r#read(var_name)?,
                 Opcode::Renum => return self.r#renum(),
                 Opcode::Restore(addr) => self.r#restore(addr)?,
                 Opcode::Return => self.r#return()?,
@@ -468,17 +1060,37 @@ impl Runtime {
                 Opcode::Swap => self.r#swap()?,
                 Opcode::Troff => self.r#troff(),
                 Opcode::Tron => self.r#tron(),
+                Opcode::Wait => {
+                    if let Some(event) = self.r#wait()? {
+                        return Ok(event);
+                    }
+                }
 
                 Opcode::Neg => self.stack.pop_1_push(&Operation::negate)?,
-                Opcode::Pow => self.stack.pop_2_push(&Operation::power)?,
-                Opcode::Mul => self.stack.pop_2_push(&Operation::multiply)?,
+                Opcode::Pow => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::power(overflow, lhs, rhs))?,
+                Opcode::Mul => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::multiply(overflow, lhs, rhs))?,
                 Opcode::Div => self.stack.pop_2_push(&Operation::divide)?,
                 Opcode::DivInt => self.stack.pop_2_push(&Operation::divint)?,
                 Opcode::Mod => self.stack.pop_2_push(&Operation::remainder)?,
-                Opcode::Add => self.stack.pop_2_push(&Operation::sum)?,
-                Opcode::Sub => self.stack.pop_2_push(&Operation::subtract)?,
-                Opcode::Eq => self.stack.pop_2_push(&Operation::equal)?,
-                Opcode::NotEq => self.stack.pop_2_push(&Operation::not_equal)?,
+                Opcode::Add if auto_str => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::sum_auto_str(overflow, lhs, rhs))?,
+                Opcode::Add => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::sum(overflow, lhs, rhs))?,
+                Opcode::Sub => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::subtract(overflow, lhs, rhs))?,
+                Opcode::Eq => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::equal(epsilon, lhs, rhs))?,
+                Opcode::NotEq => self
+                    .stack
+                    .pop_2_push(&|lhs, rhs| Operation::not_equal(epsilon, lhs, rhs))?,
                 Opcode::Lt => self.stack.pop_2_push(&Operation::less)?,
                 Opcode::LtEq => self.stack.pop_2_push(&Operation::less_equal)?,
                 Opcode::Gt => self.stack.pop_2_push(&Operation::greater)?,
@@ -498,7 +1110,7 @@ impl Runtime {
                 Opcode::Cint => self.stack.pop_1_push(&Function::cint)?,
                 Opcode::Cos => self.stack.pop_1_push(&Function::cos)?,
                 Opcode::Csng => self.stack.pop_1_push(&Function::csng)?,
-                Opcode::Date => self.stack.push(Function::date()?)?,
+                Opcode::Date => self.stack.push(Function::date(self.now())?)?,
                 Opcode::Exp => self.stack.pop_1_push(&Function::exp)?,
                 Opcode::Fix => self.stack.pop_1_push(&Function::fix)?,
                 Opcode::Hex => self.stack.pop_1_push(&Function::hex)?,
@@ -526,7 +1138,8 @@ impl Runtime {
                 Opcode::Right => self.stack.pop_2_push(&Function::right)?,
                 Opcode::Rnd => {
                     let vec = self.stack.pop_vec()?;
-                    self.stack.push(Function::rnd(&mut self.rand, vec)?)?;
+                    self.stack
+                        .push(Function::rnd(self.rnd_algo, &mut self.rand, vec)?)?;
                 }
                 Opcode::Spc => self.stack.pop_1_push(&Function::spc)?,
                 Opcode::Sgn => self.stack.pop_1_push(&Function::sgn)?,
@@ -539,7 +1152,7 @@ impl Runtime {
                     self.stack.push(Function::tab(self.print_col, val)?)?;
                 }
                 Opcode::Tan => self.stack.pop_1_push(&Function::tan)?,
-                Opcode::Time => self.stack.push(Function::time()?)?,
+                Opcode::Time => self.stack.push(Function::time(self.now())?)?,
                 Opcode::Val => self.stack.pop_1_push(&Function::val)?,
             }
         }
@@ -555,12 +1168,18 @@ impl Runtime {
         self.program.restore_data(0);
         self.stack.clear();
         self.vars.clear();
+        for (var_name, value) in &self.presets {
+            let _ = self.vars.store(var_name, value.clone());
+        }
         self.functions.clear();
         self.cont = State::Stopped;
     }
 
     fn r#cls(&mut self) -> Result<Event> {
-        Ok(Event::Cls)
+        match i16::try_from(self.stack.pop()?)? {
+            mode @ 0..=2 => Ok(Event::Cls(mode as u8)),
+            _ => Err(error!(IllegalFunctionCall)),
+        }
     }
 
     fn r#cont(&mut self) -> Result<Option<Event>> {
@@ -571,6 +1190,7 @@ impl Runtime {
             self.state = State::Stopped;
             std::mem::swap(&mut self.cont, &mut self.state);
             self.pc = self.cont_pc;
+            self.last_pc = None;
         } else {
             return Err(error!(CantContinue));
         }
@@ -619,7 +1239,9 @@ impl Runtime {
         if from == Some(0) && to == Some(LineNumber::max_value()) {
             return Err(error!(IllegalFunctionCall));
         }
+        let snapshot = self.listing.clone();
         if self.listing.remove_range(from..=to) {
+            self.push_undo(snapshot);
             self.dirty = true;
             self.state = State::Stopped;
         }
@@ -638,6 +1260,34 @@ impl Runtime {
         Event::Stopped
     }
 
+    fn r#exit(&mut self) -> Result<Event> {
+        let code = i16::try_from(self.stack.pop()?)?;
+        self.r#end();
+        Ok(Event::Exit(code as i32))
+    }
+
+    fn r#extern(&mut self, fn_name: Rc<str>) -> Result<()> {
+        let args: Vec<Val> = self.stack.pop_vec()?.drain(..).collect();
+        let val = match self.externs.get(&fn_name) {
+            Some((arity, function)) if arity.contains(&args.len()) => function(args)?,
+            Some(_) => return Err(error!(IllegalFunctionCall; "WRONG NUMBER OF ARGUMENTS")),
+            None => return Err(error!(UndefinedUserFunction)),
+        };
+        self.stack.push(val)
+    }
+
+    fn r#exit_for(&mut self, addr: Address) -> Result<()> {
+        match self.stack.pop() {
+            Ok(Val::Next(_)) => {}
+            Ok(_) | Err(_) => return Err(error!(NextWithoutFor)),
+        }
+        for _ in 0..3 {
+            self.stack.pop()?;
+        }
+        self.pc = addr;
+        Ok(())
+    }
+
     fn r#fn(&mut self, fn_name: Rc<str>) -> Result<()> {
         let mut args = self.stack.pop_vec()?;
         if let Some((arity, addr)) = self.functions.get(&fn_name) {
@@ -659,11 +1309,13 @@ impl Runtime {
     fn r#input(&mut self, var_name: Rc<str>) -> Result<Option<Event>> {
         if let State::Running = self.state {
             self.state = State::Input;
+            self.input_redo.clear();
             self.pc -= 1;
             return Ok(Some(Event::Running));
         } else if let State::InputRunning = self.state {
             if var_name.is_empty() {
                 self.state = State::Running;
+                self.input_redo.clear();
                 self.stack.pop()?;
                 self.stack.pop()?;
                 self.stack.pop()?;
@@ -688,6 +1340,26 @@ impl Runtime {
         Err(error!(InternalError))
     }
 
+    fn r#letdate(&mut self) -> Result<()> {
+        let s = Rc::<str>::try_from(self.stack.pop()?)?;
+        let date = ["%m-%d-%Y", "%m/%d/%Y"]
+            .iter()
+            .find_map(|fmt| NaiveDate::parse_from_str(s.trim(), fmt).ok())
+            .ok_or_else(|| error!(IllegalFunctionCall))?;
+        self.clock_offset = date.and_time(self.now().time()) - self.clock.now();
+        Ok(())
+    }
+
+    fn r#lettime(&mut self) -> Result<()> {
+        let s = Rc::<str>::try_from(self.stack.pop()?)?;
+        let time = ["%H:%M:%S", "%H:%M"]
+            .iter()
+            .find_map(|fmt| NaiveTime::parse_from_str(s.trim(), fmt).ok())
+            .ok_or_else(|| error!(IllegalFunctionCall))?;
+        self.clock_offset = self.now().date().and_time(time) - self.clock.now();
+        Ok(())
+    }
+
     fn r#letmid(&mut self) -> Result<()> {
         let pos = usize::try_from(self.stack.pop()?)?;
         let mut len = usize::try_from(self.stack.pop()?)?;
@@ -726,6 +1398,9 @@ impl Runtime {
                 self.r#end();
                 if self.pc < self.entry_address {
                     Err(error!(IllegalDirect))
+                } else if let Some(listing) = self.read_program(&s)? {
+                    self.set_listing(listing, false);
+                    Ok(Event::Stopped)
                 } else {
                     Ok(Event::Load(s.to_string()))
                 }
@@ -738,19 +1413,31 @@ impl Runtime {
         match self.stack.pop()? {
             Val::String(s) => {
                 self.r#end();
-                Ok(Event::Run(s.to_string()))
+                if let Some(listing) = self.read_program(&s)? {
+                    self.set_listing(listing, true);
+                    Ok(Event::Running)
+                } else {
+                    Ok(Event::Run(s.to_string()))
+                }
             }
             _ => Err(error!(TypeMismatch)),
         }
     }
 
     fn r#new_(&mut self) -> Event {
+        if !self.listing.is_empty() {
+            self.push_undo(self.listing.clone());
+        }
+        self.new_program();
+        Event::Stopped
+    }
+
+    fn new_program(&mut self) {
         self.r#clear();
         self.listing.clear();
         self.dirty = true;
         self.state = State::Stopped;
         self.tron = false;
-        Event::Stopped
     }
 
     fn r#next(&mut self, next_name: Rc<str>) -> Result<()> {
@@ -767,7 +1454,7 @@ impl Runtime {
                     continue;
                 }
                 let mut current = self.vars.fetch(&var_name);
-                current = Operation::sum(current, step_val.clone())?;
+                current = Operation::sum(OverflowMode::Error, current, step_val.clone())?;
                 self.vars.store(&var_name, current.clone())?;
                 if let Ok(step) = f64::try_from(step_val.clone()) {
                     let done = Val::Integer(-1)
@@ -803,7 +1490,7 @@ impl Runtime {
         Ok(())
     }
 
-    fn r#print(&mut self) -> Result<Event> {
+    fn r#print(&mut self) -> Result<()> {
         let item = self.stack.pop()?;
         let val_str = match item {
             Val::String(s) => s,
@@ -811,15 +1498,27 @@ impl Runtime {
         };
         for ch in val_str.chars() {
             match ch {
-                '\n' => self.print_col = 0,
+                '\n' | '\r' => self.print_col = 0,
+                '\t' => self.print_col = (self.print_col / self.tab_width + 1) * self.tab_width,
+                '\x08' => self.print_col = self.print_col.saturating_sub(1),
+                '\0' => {}
                 _ => self.print_col += 1,
             }
         }
-        Ok(Event::Print(val_str.to_string()))
+        self.print_buf.push_str(&val_str);
+        Ok(())
     }
 
-    fn r#read(&mut self) -> Result<()> {
-        let val = self.program.read_data()?;
+    fn r#read(&mut self, var_name: Rc<str>) -> Result<()> {
+        let (val, text, line_number) = self.program.read_data()?;
+        let is_string = self.vars.is_string(&var_name);
+        let val = match val {
+            Val::String(_) if !is_string => {
+                return Err(error!(SyntaxError, line_number));
+            }
+            Val::Integer(_) | Val::Single(_) | Val::Double(_) if is_string => Val::String(text),
+            _ => val,
+        };
         self.stack.push(val)
     }
 
@@ -833,7 +1532,9 @@ impl Runtime {
         let step = u16::try_from(self.stack.pop()?)?;
         let old_start = u16::try_from(self.stack.pop()?)?;
         let new_start = u16::try_from(self.stack.pop()?)?;
+        let snapshot = self.listing.clone();
         self.listing.renum(new_start, old_start, step)?;
+        self.push_undo(snapshot);
 
         self.state = State::Stopped;
         Ok(self.r#end())
@@ -879,6 +1580,17 @@ impl Runtime {
                 self.r#end();
                 if self.pc < self.entry_address {
                     Err(error!(IllegalDirect))
+                } else if let Some(filesystem) = &mut self.filesystem {
+                    if self.listing.is_empty() {
+                        return Err(error!(IllegalFunctionCall));
+                    }
+                    let mut data = String::new();
+                    for line in self.listing.lines() {
+                        data.push_str(&line.to_string());
+                        data.push('\n');
+                    }
+                    filesystem.write(&s, &data)?;
+                    Ok(Event::Stopped)
                 } else {
                     Ok(Event::Save(s.to_string()))
                 }
@@ -913,10 +1625,34 @@ impl Runtime {
         self.tron = true;
         self.tr = self.program.line_number_for(self.pc - 1);
     }
+
+    fn r#wait(&mut self) -> Result<Option<Event>> {
+        let xor = self.stack.pop()?;
+        let (port, mask) = self.stack.pop_2()?;
+        u16::try_from(port.clone())?;
+        let mask_num = u16::try_from(mask.clone())?;
+        let xor_num = u16::try_from(xor.clone())?;
+        if mask_num > 255 || xor_num > 255 {
+            return Err(error!(IllegalFunctionCall));
+        }
+        // There are no ports so every one reads as zero.
+        if xor_num & mask_num != 0 {
+            return Ok(None);
+        }
+        self.stack.push(port)?;
+        self.stack.push(mask)?;
+        self.stack.push(xor)?;
+        self.pc -= 1;
+        Ok(Some(Event::Running))
+    }
 }
 
 type RuntimeStack = Stack<Val>;
 
+pub type InputValidator = Box<dyn Fn(&str) -> std::result::Result<String, String>>;
+
+pub type ExternFunction = Box<dyn Fn(Vec<Val>) -> Result<Val>>;
+
 trait RuntimeStackTrait<T> {
     fn pop_1_push<F: Fn(Val) -> Result<Val>>(&mut self, func: &F) -> Result<()>;
     fn pop_2_push<F: Fn(Val, Val) -> Result<Val>>(&mut self, func: &F) -> Result<()>;
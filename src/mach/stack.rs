@@ -8,6 +8,7 @@ type Result<T> = std::result::Result<T, Error>;
 #[derive(Clone)]
 pub struct Stack<T> {
     overflow_message: &'static str,
+    max_len: usize,
     vec: Vec<T>,
 }
 
@@ -21,14 +22,19 @@ impl<T> Stack<T> {
     pub fn new(overflow_message: &'static str) -> Stack<T> {
         Stack {
             overflow_message,
+            max_len: u16::MAX as usize,
             vec: vec![],
         }
     }
-    fn max_len(&self) -> usize {
-        u16::max_value() as usize
+    /// Most items allowed before an overflow error.
+    pub fn capacity(&self) -> usize {
+        self.max_len
+    }
+    pub fn set_capacity(&mut self, max_len: usize) {
+        self.max_len = max_len;
     }
     fn overflow_check(&self) -> Result<()> {
-        if self.vec.len() > self.max_len() {
+        if self.vec.len() > self.max_len {
             Err(error!(OutOfMemory; self.overflow_message))
         } else {
             Ok(())
@@ -57,7 +63,7 @@ impl<T> Stack<T> {
         self.vec.is_empty()
     }
     pub fn is_full(&self) -> bool {
-        self.vec.len() > self.max_len() - 32
+        self.vec.len() > self.max_len.saturating_sub(32)
     }
     pub fn last(&self) -> Option<&T> {
         self.vec.last()
@@ -33,7 +33,7 @@ impl std::fmt::Display for Val {
             Double(num) => {
                 let s = format!("{}", num);
                 if s.chars().filter(char::is_ascii_digit).count() > 17 {
-                    format!("{:E}", num)
+                    format!("{:E}", num).replace('E', "D")
                 } else {
                     format!("{}", num)
                 }
@@ -50,6 +50,31 @@ impl std::fmt::Display for Val {
     }
 }
 
+/// Numbers compare by value and strings lexically, as in BASIC.
+/// Numbers of different types with the same value order as equal
+/// even though `==` still compares types. Strings and numbers are
+/// unordered. `Return` and `Next` are only ordered when equal.
+impl PartialOrd for Val {
+    fn partial_cmp(&self, other: &Val) -> Option<std::cmp::Ordering> {
+        use Val::*;
+        match (self, other) {
+            (String(l), String(r)) => l.partial_cmp(r),
+            (Integer(l), Integer(r)) => l.partial_cmp(r),
+            (Single(l), Single(r)) => l.partial_cmp(r),
+            (Double(l), Double(r)) => l.partial_cmp(r),
+            (Integer(_) | Single(_) | Double(_), Integer(_) | Single(_) | Double(_)) => {
+                let lhs = f64::try_from(self.clone()).ok()?;
+                let rhs = f64::try_from(other.clone()).ok()?;
+                lhs.partial_cmp(&rhs)
+            }
+            (Return(l), Return(r)) | (Next(l), Next(r)) if l == r => {
+                Some(std::cmp::Ordering::Equal)
+            }
+            _ => None,
+        }
+    }
+}
+
 impl TryFrom<LineNumber> for Val {
     type Error = Error;
     fn try_from(line_number: LineNumber) -> std::result::Result<Self, Self::Error> {
@@ -85,7 +110,7 @@ impl TryFrom<Val> for u16 {
             }
             Val::Single(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= u16::max_value() as f32 {
+                if num >= 0.0 && num <= u16::MAX as f32 {
                     Ok(num as u16)
                 } else {
                     Err(error!(Overflow))
@@ -93,7 +118,7 @@ impl TryFrom<Val> for u16 {
             }
             Val::Double(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= u16::max_value() as f64 {
+                if num >= 0.0 && num <= u16::MAX as f64 {
                     Ok(num as u16)
                 } else {
                     Err(error!(Overflow))
@@ -111,7 +136,7 @@ impl TryFrom<Val> for i16 {
             Val::Integer(num) => Ok(num),
             Val::Single(num) => {
                 let num = num.floor();
-                if num >= i16::min_value() as f32 && num <= i16::max_value() as f32 {
+                if num >= i16::MIN as f32 && num <= i16::MAX as f32 {
                     Ok(num as i16)
                 } else {
                     Err(error!(Overflow))
@@ -119,7 +144,7 @@ impl TryFrom<Val> for i16 {
             }
             Val::Double(num) => {
                 let num = num.floor();
-                if num >= i16::min_value() as f64 && num <= i16::max_value() as f64 {
+                if num >= i16::MIN as f64 && num <= i16::MAX as f64 {
                     Ok(num as i16)
                 } else {
                     Err(error!(Overflow))
@@ -143,7 +168,7 @@ impl TryFrom<Val> for u32 {
             }
             Val::Single(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= u32::max_value() as f32 {
+                if num >= 0.0 && num <= u32::MAX as f32 {
                     Ok(num as u32)
                 } else {
                     Err(error!(Overflow))
@@ -151,7 +176,7 @@ impl TryFrom<Val> for u32 {
             }
             Val::Double(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= u32::max_value() as f64 {
+                if num >= 0.0 && num <= u32::MAX as f64 {
                     Ok(num as u32)
                 } else {
                     Err(error!(Overflow))
@@ -175,7 +200,7 @@ impl TryFrom<Val> for usize {
             }
             Val::Single(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= usize::max_value() as f32 {
+                if num >= 0.0 && num <= usize::MAX as f32 {
                     Ok(num as usize)
                 } else {
                     Err(error!(Overflow))
@@ -183,7 +208,7 @@ impl TryFrom<Val> for usize {
             }
             Val::Double(num) => {
                 let num = num.floor();
-                if num >= 0.0 && num <= usize::max_value() as f64 {
+                if num >= 0.0 && num <= usize::MAX as f64 {
                     Ok(num as usize)
                 } else {
                     Err(error!(Overflow))
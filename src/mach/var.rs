@@ -1,7 +1,7 @@
 use super::{Stack, Val};
 use crate::error;
 use crate::lang::Error;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fmt::Write;
 use std::rc::Rc;
@@ -10,11 +10,15 @@ type Result<T> = std::result::Result<T, Error>;
 
 /// ## Variable memory
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Var {
     vars: HashMap<Rc<str>, Val>,
+    assigned: HashSet<Rc<str>>,
     dims: HashMap<Rc<str>, Vec<i16>>,
     types: [VarType; 26],
+    strict: bool,
+    retain_zero: bool,
+    default_dim: Option<i16>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq)]
@@ -31,8 +35,32 @@ impl Var {
         Var::default()
     }
 
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Keep variables assigned zero or empty instead of freeing them.
+    pub fn set_retain_zero(&mut self, retain_zero: bool) {
+        self.retain_zero = retain_zero;
+    }
+
+    /// Upper bound for arrays used without `DIM`, 10 if not set.
+    pub fn set_default_dim(&mut self, dim: usize) {
+        self.default_dim = Some(dim.min(i16::MAX as usize) as i16);
+    }
+
+    /// Number of values held in memory, including array elements.
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
     pub fn clear(&mut self) {
         self.vars.clear();
+        self.assigned.clear();
         self.dims.clear();
         self.types = Default::default();
     }
@@ -61,6 +89,10 @@ impl Var {
                 for idx in (from as usize - 'A' as usize)..=(to as usize - 'A' as usize) {
                     self.types[idx] = var_type.clone();
                 }
+                self.assigned.retain(|k| {
+                    !k.chars().last().unwrap_or('-').is_ascii_alphabetic()
+                        || !(from..=to).contains(&k.chars().next().unwrap_or('-'))
+                });
                 self.vars.retain(|k, v| {
                     if !k.chars().last().unwrap_or('-').is_ascii_alphabetic() {
                         true
@@ -124,6 +156,17 @@ impl Var {
         Ok(self.fetch(&key))
     }
 
+    pub fn array_len(&self, var_name: &str) -> Option<usize> {
+        self.dims
+            .get(var_name)
+            .map(|vi| vi.iter().map(|num| *num as usize + 1).product())
+    }
+
+    pub fn clear_scalar(&mut self, var_name: &Rc<str>) {
+        self.vars.remove(var_name);
+        self.assigned.remove(var_name);
+    }
+
     pub fn erase_array(&mut self, var_name: &Rc<str>) -> Result<()> {
         if self.dims.remove(var_name).is_none() {
             return Err(error!(IllegalFunctionCall; "ARRAY NOT DIMENSIONED"));
@@ -131,6 +174,7 @@ impl Var {
         let mut pattern = var_name.to_string();
         pattern.push(',');
         self.vars.retain(|k, _| !k.starts_with(&pattern));
+        self.assigned.retain(|k| !k.starts_with(&pattern));
         Ok(())
     }
 
@@ -138,27 +182,34 @@ impl Var {
         if self.dims.contains_key(var_name) {
             return Err(error!(RedimensionedArray));
         }
-        let vi = self.vec_val_to_vec_i16(arr)?;
+        let vi = self.vec_val_to_vec_i16(var_name, arr)?;
+        let mut elements: usize = 1;
+        for num in &vi {
+            elements = elements.saturating_mul(*num as usize + 1);
+        }
+        if elements > i16::MAX as usize {
+            return Err(error!(OutOfMemory; "ARRAY TOO LARGE"));
+        }
         self.dims.insert(var_name.clone(), vi);
         Ok(())
     }
 
     fn build_array_key(&mut self, var_name: &Rc<str>, arr: Stack<Val>) -> Result<Rc<str>> {
-        let requested = self.vec_val_to_vec_i16(arr)?;
+        let requested = self.vec_val_to_vec_i16(var_name, arr)?;
         let dimensioned = match self.dims.get(var_name) {
             Some(vec_num) => vec_num,
+            None if self.strict => {
+                return Err(error!(SubscriptOutOfRange; "ARRAY NOT DIMENSIONED"));
+            }
             None => self
                 .dims
                 .entry(var_name.clone())
-                .or_insert_with(|| vec![10; requested.len()]),
+                .or_insert_with(|| vec![self.default_dim.unwrap_or(10); requested.len()]),
         };
-        if dimensioned.len() != requested.len() {
-            return Err(error!(SubscriptOutOfRange));
-        }
-        for (r, d) in requested.iter().zip(dimensioned) {
-            if r > d {
-                return Err(error!(SubscriptOutOfRange));
-            }
+        if dimensioned.len() != requested.len()
+            || requested.iter().zip(dimensioned).any(|(r, d)| r > d)
+        {
+            return Err(Var::subscript_error(var_name, &requested));
         }
         let mut s: String = format!("{}", var_name);
         s.push_str(&requested.iter().fold(String::new(), |mut output, b| {
@@ -170,24 +221,24 @@ impl Var {
         Ok(s.into())
     }
 
-    fn vec_val_to_vec_i16(&self, mut arr: Stack<Val>) -> Result<Vec<i16>> {
+    fn vec_val_to_vec_i16(&self, var_name: &Rc<str>, mut arr: Stack<Val>) -> Result<Vec<i16>> {
         let mut vec_i16: Vec<i16> = vec![];
         for val in arr.drain(..) {
-            match i16::try_from(val) {
-                Ok(num) => {
-                    if num < 0 {
-                        return Err(error!(SubscriptOutOfRange));
-                    }
-                    vec_i16.push(num)
-                }
-                Err(e) => return Err(e),
-            }
+            vec_i16.push(i16::try_from(val)?);
+        }
+        if vec_i16.iter().any(|num| *num < 0) {
+            return Err(Var::subscript_error(var_name, &vec_i16));
         }
         Ok(vec_i16)
     }
 
+    fn subscript_error(var_name: &Rc<str>, subscripts: &[i16]) -> Error {
+        let subscripts: Vec<String> = subscripts.iter().map(|num| num.to_string()).collect();
+        error!(SubscriptOutOfRange; &format!("{}({})", var_name, subscripts.join(",")))
+    }
+
     pub fn store(&mut self, var_name: &Rc<str>, value: Val) -> Result<()> {
-        if self.vars.len() > u16::max_value() as usize {
+        if self.vars.len() > u16::MAX as usize {
             return Err(error!(OutOfMemory));
         }
         if var_name.ends_with('!') {
@@ -213,14 +264,38 @@ impl Var {
         }
     }
 
+    /// True if the variable holds strings by its suffix or `DEFSTR`.
+    pub fn is_string(&self, var_name: &str) -> bool {
+        match var_name.chars().last() {
+            Some('$') => true,
+            Some('!') | Some('#') | Some('%') => false,
+            _ => match var_name.chars().next() {
+                Some(idx) if idx.is_ascii_uppercase() => {
+                    matches!(self.types[idx as usize - 'A' as usize], VarType::String)
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// True if the variable was ever assigned, even if only to zero.
+    pub fn is_set(&self, var_name: &str) -> bool {
+        self.assigned.contains(var_name)
+    }
+
     fn update_val(&mut self, var_name: &Rc<str>, value: Val) {
-        if match &value {
-            Val::String(s) => s.is_empty(),
-            Val::Integer(n) => *n == 0,
-            Val::Single(n) => *n == 0.0,
-            Val::Double(n) => *n == 0.0,
-            Val::Return(_) | Val::Next(_) => false,
-        } {
+        if !self.assigned.contains(var_name) {
+            self.assigned.insert(var_name.clone());
+        }
+        if !self.retain_zero
+            && match &value {
+                Val::String(s) => s.is_empty(),
+                Val::Integer(n) => *n == 0,
+                Val::Single(n) => *n == 0.0,
+                Val::Double(n) => *n == 0.0,
+                Val::Return(_) | Val::Next(_) => false,
+            }
+        {
             self.vars.remove(var_name);
         } else {
             match self.vars.get_mut(var_name) {
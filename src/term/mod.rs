@@ -1,18 +1,16 @@
 extern crate ansi_term;
-extern crate crc;
 extern crate ctrlc;
 extern crate linefeed;
 extern crate mortal;
 extern crate reqwest;
-use crate::mach::{Event, Listing, Runtime};
+use crate::mach::{Event, Listing, PatchAction, Runtime};
 use crate::{error, lang::Error};
 use ansi_term::Style;
-use crc::Hasher32;
 use linefeed::{
     Command, Completer, Completion, Function, Interface, Prompter, ReadResult, Signal, Terminal,
 };
 use std::fs;
-use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::io::{BufReader, ErrorKind, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
@@ -33,12 +31,14 @@ pub fn main() {
         int_moved.store(true, Ordering::SeqCst);
     })
     .expect("Error setting Ctrl-C handler");
-    if let Err(error) = main_loop(interrupted, filename) {
-        eprintln!("{}", error);
+    match main_loop(interrupted, filename) {
+        Ok(0) => {}
+        Ok(code) => std::process::exit(code),
+        Err(error) => eprintln!("{}", error),
     }
 }
 
-fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<()> {
+fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<i32> {
     let terminal = mortal::Terminal::new()?;
     let mut runtime = Runtime::default();
     let command = Interface::new("BASIC")?;
@@ -52,7 +52,7 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
         match load(&filename, true, false) {
             Ok(listing) => {
                 if listing.is_empty() {
-                    return Ok(());
+                    return Ok(0);
                 }
                 runtime.set_prompt("");
                 runtime.set_listing(listing, true);
@@ -62,7 +62,7 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
                     "{}\n",
                     Style::new().bold().paint(error.to_string())
                 ))?;
-                return Ok(());
+                return Ok(0);
             }
         }
     }
@@ -75,7 +75,7 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
         match runtime.execute(5000) {
             Event::Stopped => {
                 if !filename.is_empty() {
-                    return Ok(());
+                    return Ok(0);
                 }
                 let saved_completer = command.completer();
                 command.set_completer(Arc::new(LineCompleter::new(runtime.get_listing())));
@@ -88,11 +88,16 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
                     command.add_history_unique(string);
                 }
             }
-            Event::Input(prompt, caps) => {
+            Event::Input(prompt, caps, redo, newline) => {
                 let input = if caps { &input_caps } else { &input_full };
                 input.set_prompt(&prompt)?;
+                input.set_buffer(&redo)?;
                 match input.read_line()? {
                     ReadResult::Input(string) => {
+                        if !newline {
+                            let col = prompt.chars().count() + string.chars().count();
+                            command.write_fmt(format_args!("\x1b[1A\x1b[{}C", col))?;
+                        }
                         if runtime.enter(&string) {
                             input.add_history_unique(string);
                         }
@@ -143,7 +148,12 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
                     Style::new().bold().paint(error.to_string())
                 ))?,
             },
-            Event::Cls => {
+            Event::Exit(code) => {
+                if !filename.is_empty() {
+                    return Ok(code);
+                }
+            }
+            Event::Cls(_) => {
                 terminal.clear_screen()?;
             }
             Event::Inkey => {
@@ -184,7 +194,7 @@ fn main_loop(interrupted: Arc<AtomicBool>, filename: String) -> std::io::Result<
             }
         }
     }
-    Ok(())
+    Ok(0)
 }
 
 struct CapsFunction;
@@ -285,25 +295,12 @@ fn save(listing: &Listing, filename: &str) -> Result<(), Error> {
     Ok(())
 }
 
-fn parse_filename(filename: &str, index: usize) -> Result<String, Error> {
-    let filename = filename.trim();
-    if filename.len() < 3 || !filename.starts_with('"') || !filename.ends_with('"') {
-        return Err(error!(BadFileName; &format!(
-            "In line {} of the patch file.",
-            index + 1
-        )));
-    }
-    let filename = filename[1..filename.len() - 1].to_string();
-    match fs::metadata(&filename) {
-        Ok(_metadata) => {
-            println!("Saving to {}", filename);
-            Err(error!(FileAlreadyExists; &format!(
-                "In line {} of the patch file.", index+1
-            )))
-        }
+fn check_filename(filename: &str) -> Result<(), Error> {
+    match fs::metadata(filename) {
+        Ok(_metadata) => Err(error!(FileAlreadyExists; filename)),
         Err(e) => {
             if let ErrorKind::NotFound = e.kind() {
-                Ok(filename)
+                Ok(())
             } else {
                 Err(error!(InternalError; &e.to_string()))
             }
@@ -355,86 +352,62 @@ fn load2(
     allow_patch: bool,
     ignore_errors: bool,
 ) -> Result<Listing, Error> {
-    let mut first_listing = Listing::default();
     let mut listing = Listing::default();
-    let mut patching = false;
-    let mut filename = String::default();
-    for (index, line) in reader.lines().enumerate() {
-        match line {
-            Err(error) => return Err(error!(InternalError; error.to_string().as_str())),
-            Ok(line) => {
-                if allow_patch && index == 0 && (line.starts_with('"') || line.starts_with('\'')) {
-                    patching = true;
-                    println!("Patch mode.\n");
-                }
-                if patching && line.starts_with('\'') {
-                    println!("{}", line[1..].trim());
-                    continue;
-                }
-                if patching && line.starts_with('"') {
-                    let mut parts: Vec<&str> = line.split_ascii_whitespace().collect();
-                    if parts.len() == 1 {
-                        filename = parse_filename(parts.pop().unwrap(), index)?;
-                    } else if parts.len() == 3 {
-                        if !filename.is_empty() {
-                            println!("Saving to {}", filename);
-                            save(&listing, &filename)?;
-                            println!();
-                        }
-                        if first_listing.is_empty() {
-                            std::mem::swap(&mut listing, &mut first_listing)
-                        }
-                        let url = parts.pop().unwrap();
-                        let crc = parts.pop().unwrap();
-                        filename = parse_filename(parts.pop().unwrap(), index)?;
-                        println!("Retrieving from {}", url);
-                        listing = load(url, false, true)?;
-                        let crc = match u32::from_str_radix(crc, 16) {
-                            Ok(crc) => crc,
-                            Err(_) => {
-                                return Err(error!(SyntaxError; &format!(
-                                    "Unable to parse crc info in line {} of the patch file.",
-                                    index + 1
-                                )));
-                            }
-                        };
-                        let mut digest = crc::crc32::Digest::new(crc::crc32::IEEE);
-                        for line in listing.lines() {
-                            digest.write(line.to_string().as_bytes());
-                        }
-                        let digest = digest.sum32();
-                        if digest != crc {
-                            return Err(error!(SyntaxError; &format!(
-                                "Expected CRC {:08X} got {:08X} in line {} of the patch file.",
-                                crc, digest, index + 1
-                            )));
-                        }
-                    } else {
-                        return Err(error!(SyntaxError; &format!(
-                            "Unable to parse info in line {} of the patch file.",
-                            index + 1
-                        )));
-                    }
-                    continue;
-                }
-                if let Err(error) = listing.load_str(&line) {
-                    if !ignore_errors {
-                        return Err(error.message(&format!("In line {} of the file.", index + 1)));
-                    }
-                }
+    let mut text = String::new();
+    if let Err(error) = reader.read_to_string(&mut text) {
+        return Err(error!(InternalError; error.to_string().as_str()));
+    }
+    if allow_patch {
+        let actions = listing.apply_patch(&text)?;
+        if actions.is_empty() {
+            return Ok(listing);
+        }
+        return patch(actions);
+    }
+    for (index, line) in Listing::logical_lines(&text) {
+        if let Err(error) = listing.load_str(&line) {
+            if !ignore_errors {
+                return Err(error.message(&format!("In line {} of the file.", index + 1)));
             }
         }
     }
-    if patching {
-        println!("Saving to {}", filename);
-        save(&listing, &filename)?;
-        println!();
-        if !first_listing.is_empty() {
-            Ok(first_listing)
-        } else {
-            Ok(listing)
+    Ok(listing)
+}
+
+fn patch(actions: Vec<PatchAction>) -> Result<Listing, Error> {
+    println!("Patch mode.\n");
+    let mut first_listing: Option<Listing> = None;
+    let mut listing = Listing::default();
+    for action in actions {
+        match action {
+            PatchAction::Message(s) => println!("{}", s),
+            PatchAction::Retrieve {
+                index,
+                filename,
+                crc,
+                url,
+                lines,
+            } => {
+                check_filename(&filename)?;
+                println!("Retrieving from {}", url);
+                listing = load(&url, false, true)?;
+                listing.patch(crc, index, &lines)?;
+                println!("Saving to {}", filename);
+                save(&listing, &filename)?;
+                println!();
+                first_listing.get_or_insert_with(|| listing.clone());
+            }
+            PatchAction::Create {
+                filename, lines, ..
+            } => {
+                check_filename(&filename)?;
+                listing = Listing::default();
+                listing.load_patch_lines(&lines)?;
+                println!("Saving to {}", filename);
+                save(&listing, &filename)?;
+                println!();
+            }
         }
-    } else {
-        Ok(listing)
     }
+    Ok(first_listing.unwrap_or(listing))
 }
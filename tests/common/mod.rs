@@ -27,16 +27,17 @@ pub fn exec_n(runtime: &mut Runtime, cycles: usize) -> String {
             Event::Print(ps) => {
                 s.push_str(ps);
             }
-            Event::Input(ps, _) => {
+            Event::Input(ps, _, _, _) => {
                 s.push_str(ps);
                 break;
             }
             Event::List((ls, _columns)) => {
                 s.push_str(&format!("{}\n", ls));
             }
-            Event::Cls => {
+            Event::Cls(_) => {
                 s.push('\n');
             }
+            Event::Exit(_) => {}
         }
         match event {
             Event::Running => prev_running = true,
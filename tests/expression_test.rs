@@ -1,5 +1,5 @@
 mod common;
-use basic::mach::Runtime;
+use basic::mach::{OverflowMode, Runtime, Val};
 use common::*;
 
 #[test]
@@ -27,6 +27,69 @@ fn test_int_division() {
     assert_eq!(exec(&mut r), " 2.5 \n");
 }
 
+#[test]
+fn test_comparison_truth_values() {
+    let mut r = Runtime::default();
+    r.enter(r#"PRINT (1<2)"#);
+    assert_eq!(exec(&mut r), "-1 \n");
+    r.enter(r#"PRINT (1>2)"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+    r.enter(r#"A=(1<2):IF A THEN PRINT "T" ELSE PRINT "F""#);
+    assert_eq!(exec(&mut r), "T\n");
+    r.enter(r#"A=("A">"B"):PRINT A;NOT A"#);
+    assert_eq!(exec(&mut r), " 0 -1 \n");
+}
+
+#[test]
+fn test_float_epsilon() {
+    let mut r = Runtime::default();
+    r.enter(r#"PRINT 100.1+100.2=200.3;100.1+100.2<>200.3"#);
+    assert_eq!(exec(&mut r), " 0 -1 \n");
+    r.set_float_epsilon(0.001);
+    r.enter(r#"PRINT 100.1+100.2=200.3;100.1+100.2<>200.3;1.1=1.2"#);
+    assert_eq!(exec(&mut r), "-1  0  0 \n");
+}
+
+#[test]
+fn test_val_ordering() {
+    assert!(Val::Integer(1) < Val::Single(1.5));
+    assert!(Val::Double(-2.0) < Val::Integer(1));
+    assert!(Val::String("a".into()) < Val::String("b".into()));
+    assert!(Val::String("B".into()) < Val::String("a".into()));
+    assert_eq!(Val::Integer(1).partial_cmp(&Val::String("1".into())), None);
+    assert_eq!(
+        Val::Integer(1).partial_cmp(&Val::Single(1.0)),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert_ne!(Val::Integer(1), Val::Single(1.0));
+    assert_ne!(Val::Integer(1), Val::String("1".into()));
+    assert_eq!(Val::Return(1), Val::Return(1));
+    assert_eq!(Val::Return(1).partial_cmp(&Val::Return(2)), None);
+    let mut vals = [Val::Double(1.0), Val::Integer(1), Val::Single(0.5)];
+    vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(vals[0], Val::Single(0.5));
+    let mut vals = vec![Val::Single(2.5), Val::Integer(-3), Val::Double(1.0)];
+    vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(
+        vals,
+        vec![Val::Integer(-3), Val::Double(1.0), Val::Single(2.5)]
+    );
+}
+
+#[test]
+fn test_value_stack_size() {
+    let expr = format!("PRINT {}1{}", "ABS(1+".repeat(50), ")".repeat(50));
+    let mut r = Runtime::default();
+    r.enter(&expr);
+    assert_eq!(exec(&mut r), " 51 \n");
+    r.set_value_stack_size(100);
+    r.enter(&expr);
+    assert_eq!(exec(&mut r), " 51 \n");
+    r.set_value_stack_size(20);
+    r.enter(&expr);
+    assert_eq!(exec(&mut r), "?OUT OF MEMORY; STACK OVERFLOW\n");
+}
+
 #[test]
 fn test_variables() {
     let mut r = Runtime::default();
@@ -36,6 +99,54 @@ fn test_variables() {
     assert_eq!(exec(&mut r), "?OVERFLOW\n");
 }
 
+#[test]
+fn test_integer_literal_bounds() {
+    let mut r = Runtime::default();
+    r.enter(r#"PRINT 32767;32768;40000;-32768"#);
+    assert_eq!(exec(&mut r), " 32767  32768  40000 -32768 \n");
+    r.enter(r#"A%=32767:PRINT A%;32767%"#);
+    assert_eq!(exec(&mut r), " 32767  32767 \n");
+    r.enter(r#"A%=32768"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"A%=40000"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"10 PRINT 32768%"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW IN 10:10\n");
+}
+
+#[test]
+fn test_integer_overflow_mode() {
+    let mut r = Runtime::default();
+    r.enter(r#"?32767+1"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.set_integer_overflow(OverflowMode::Wrap);
+    r.enter(r#"?32767+1;-32767-2;300*300;2^16"#);
+    assert_eq!(exec(&mut r), "-32768  32767  24464  0 \n");
+    r.enter(r#"?32767+1.5"#);
+    assert_eq!(exec(&mut r), " 32768.5 \n");
+    r.enter(r#"FOR I%=32766 TO 32767:NEXT"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.set_integer_overflow(OverflowMode::Error);
+    r.enter(r#"?-32767-2"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+}
+
+#[test]
+fn test_auto_str() {
+    let mut r = Runtime::default();
+    r.enter(r#"?"X="+STR$(1)"#);
+    assert_eq!(exec(&mut r), "X= 1\n");
+    r.enter(r#"?"X="+1"#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH\n");
+    r.set_auto_str(true);
+    r.enter(r#"?"X="+1;2.5+"Y";1+2"#);
+    assert_eq!(exec(&mut r), "X= 1 2.5Y 3 \n");
+    r.set_auto_str(false);
+    r.enter(r#"?1+"Y""#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH\n");
+}
+
 #[test]
 fn test_array_basics() {
     let mut r = Runtime::default();
@@ -47,6 +158,18 @@ fn test_array_basics() {
     assert_eq!(exec(&mut r), "THE ANSWER!    2.7182817 \n");
 }
 
+#[test]
+fn test_array_default_dim() {
+    let mut r = Runtime::default();
+    r.enter(r#"A(11)=1"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; A(11)\n");
+    r.set_default_dim(20);
+    r.enter(r#"B(15)=7:PRINT B(15)"#);
+    assert_eq!(exec(&mut r), " 7 \n");
+    r.enter(r#"B(25)=1"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; B(25)\n");
+}
+
 #[test]
 fn test_hex_octal() {
     let mut r = Runtime::default();
@@ -61,3 +184,30 @@ fn test_hex_octal() {
     r.enter(r#"?&015"#);
     assert_eq!(exec(&mut r), " 13 \n");
 }
+
+#[test]
+fn test_binary() {
+    let mut r = Runtime::default();
+    r.enter(r#"PRINT &B1010"#);
+    assert_eq!(exec(&mut r), " 10 \n");
+    r.enter(r#"?&b0111111111111111"#);
+    assert_eq!(exec(&mut r), " 32767 \n");
+    r.enter(r#"?&B2"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"10 ?&b101"#);
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 PRINT &B101\n");
+}
+
+#[test]
+fn test_exponent_format() {
+    let mut r = Runtime::default();
+    r.enter(r#"?1.234567E20;-1E-20"#);
+    assert_eq!(exec(&mut r), " 1.234567E20 -1E-20 \n");
+    r.enter(r#"?12345678901234567890#;1D300;-1D-300"#);
+    assert_eq!(exec(&mut r), " 1.2345678901234567D19  1D300 -1D-300 \n");
+    r.enter(r#"?1.23456789012345D15"#);
+    assert_eq!(exec(&mut r), " 1234567890123450 \n");
+    r.enter(r#"?VAL(STR$(1D300))"#);
+    assert_eq!(exec(&mut r), " 1D300 \n");
+}
@@ -1,7 +1,19 @@
 mod common;
-use basic::mach::Runtime;
+use basic::mach::{Clock, RndAlgo, Runtime, Val};
+use chrono::{NaiveDate, NaiveDateTime};
 use common::*;
 
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now(&self) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(1981, 8, 12)
+            .unwrap()
+            .and_hms_opt(9, 5, 30)
+            .unwrap()
+    }
+}
+
 #[test]
 fn test_built_in_reserved() {
     let mut r = Runtime::default();
@@ -11,7 +23,7 @@ fn test_built_in_reserved() {
     assert_eq!(exec(&mut r), " 42 \n");
     r.enter(r#"val(0)=42"#);
     assert_eq!(exec(&mut r), "?SYNTAX ERROR; RESERVED FOR BUILT-IN\n");
-    r.enter(r#"time$="42""#);
+    r.enter(r#"inkey$="42""#);
     assert_eq!(exec(&mut r), "?SYNTAX ERROR; RESERVED FOR BUILT-IN\n");
     r.enter(r#"rnd()=42"#);
     assert_eq!(exec(&mut r), "?SYNTAX ERROR; EXPECTED EXPRESSION\n");
@@ -57,6 +69,8 @@ fn test_fn_cint() {
     let mut r = Runtime::default();
     r.enter(r#"?cint(-3.7)"#);
     assert_eq!(exec(&mut r), "-4 \n");
+    r.enter(r#"?cint(2.5);cint(3.5);cint(-2.5);cint(2.4)"#);
+    assert_eq!(exec(&mut r), " 2  4 -2  2 \n");
 }
 
 #[test]
@@ -78,6 +92,9 @@ fn test_fn_date() {
     let mut r = Runtime::default();
     r.enter(r#"?len(date$)"#);
     assert_eq!(exec(&mut r), " 10 \n");
+    r.set_clock(Box::new(FixedClock));
+    r.enter(r#"?date$"#);
+    assert_eq!(exec(&mut r), "08-12-1981\n");
 }
 
 #[test]
@@ -85,6 +102,14 @@ fn test_fn_exp() {
     let mut r = Runtime::default();
     r.enter(r#"?exp(-9.9)"#);
     assert_eq!(exec(&mut r), " 5.01747E-5 \n");
+    r.enter(r#"?exp(0);exp(1)"#);
+    assert_eq!(exec(&mut r), " 1  2.7182817 \n");
+    r.enter(r#"?exp(1000)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"?exp(1000#)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"?exp(-1000)"#);
+    assert_eq!(exec(&mut r), " 0 \n");
 }
 
 #[test]
@@ -99,6 +124,10 @@ fn test_fn_hex() {
     let mut r = Runtime::default();
     r.enter(r#"?hex$(13)"#);
     assert_eq!(exec(&mut r), "D\n");
+    r.enter(r#"?hex$(&hffff);hex$(-1);hex$(65535);&hffff"#);
+    assert_eq!(exec(&mut r), "FFFFFFFFFFFF-1 \n");
+    r.enter(r#"?hex$(70000#)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
 }
 
 #[test]
@@ -139,6 +168,12 @@ fn test_fn_len() {
     let mut r = Runtime::default();
     r.enter(r#"?len("TASTY")"#);
     assert_eq!(exec(&mut r), " 5 \n");
+    r.enter(r#"10 PRINT LEN(5)"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH IN 10:14\n");
+    r.enter(r#"10 A$=MID$("X",0)"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW IN 10:12\n");
 }
 
 #[test]
@@ -146,6 +181,10 @@ fn test_fn_log() {
     let mut r = Runtime::default();
     r.enter(r#"?log(8/37)"#);
     assert_eq!(exec(&mut r), "-1.5314764 \n");
+    r.enter(r#"?log(0)"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    r.enter(r#"?log(-1.5#)"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
 }
 
 #[test]
@@ -155,6 +194,16 @@ fn test_fn_mid() {
     assert_eq!(exec(&mut r), "TY\n");
     r.enter(r#"?mid$("TASTY",4,1)"#);
     assert_eq!(exec(&mut r), "T\n");
+    r.enter(r#"?"[";mid$("ABC",2,0);"]""#);
+    assert_eq!(exec(&mut r), "[]\n");
+    r.enter(r#"?mid$("ABC",2)"#);
+    assert_eq!(exec(&mut r), "BC\n");
+    r.enter(r#"?"[";mid$("ABC",9);"]""#);
+    assert_eq!(exec(&mut r), "[]\n");
+    r.enter(r#"?mid$("ABC",0)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"?mid$("ABC",1,-1)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
 }
 
 #[test]
@@ -171,6 +220,25 @@ fn test_fn_pos() {
     assert_eq!(exec(&mut r), "      5 \n");
     r.enter(r#"?"      ";pos(-10)"#);
     assert_eq!(exec(&mut r), "       6 \n");
+    r.enter(r#"PRINT CHR$(13);"X";:PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "\rX 1 \n");
+    r.enter(r#"PRINT "AB":PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "AB\n 0 \n");
+    r.enter(r#"PRINT "AB";:PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "AB 2 \n");
+    r.enter(r#"PRINT "AB":PRINT "C";:PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "AB\nC 1 \n");
+    r.enter(r#"?"AB";CHR$(9);POS(0)"#);
+    assert_eq!(exec(&mut r), "AB\t 8 \n");
+    r.enter(r#"?"ABC";CHR$(8);POS(0)"#);
+    assert_eq!(exec(&mut r), "ABC\x08 2 \n");
+    r.enter(r#"PRINT CHR$(0);"X";:PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "\0X 1 \n");
+    r.set_tab_width(4);
+    r.enter(r#"PRINT CHR$(9);"X";:PRINT POS(0)"#);
+    assert_eq!(exec(&mut r), "\tX 5 \n");
+    r.enter(r#"?"ABCD";CHR$(9);POS(0)"#);
+    assert_eq!(exec(&mut r), "ABCD\t 8 \n");
 }
 
 #[test]
@@ -192,11 +260,30 @@ fn test_fn_rnd() {
     assert_eq!(exec(&mut r), " 0.2008394  0.2008394  1.7587423E-2 \n");
 }
 
+#[test]
+fn test_fn_rnd_gw_basic() {
+    let mut r = Runtime::default();
+    r.set_rnd_algorithm(RndAlgo::GwBasic);
+    r.enter(r#"?rnd(-1)rnd()rnd()rnd(0)rnd(1)"#);
+    assert_eq!(
+        exec(&mut r),
+        " 0.5814289  0.49092394  0.25627422  0.25627422  0.16621703 \n"
+    );
+    r.set_rnd_algorithm(RndAlgo::WichmannHill);
+    r.enter(r#"?rnd(-1)rnd()"#);
+    assert_eq!(exec(&mut r), " 2.5737405E-2  0.6736604 \n");
+}
+
 #[test]
 fn test_fn_sgn() {
     let mut r = Runtime::default();
     r.enter(r#"?sgn(0.0);sgn(-1.0/0.0);sgn(10000000000)"#);
     assert_eq!(exec(&mut r), " 0 -1  1 \n");
+    r.enter(r#"?sgn(-0.0);sgn(1.5);sgn(-3);sgn(0);sgn(-1#)"#);
+    assert_eq!(exec(&mut r), " 0  1 -1  0 -1 \n");
+    r.enter(r#"10 ?sgn("A")"#);
+    r.enter(r#"run"#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH IN 10:14\n");
 }
 
 #[test]
@@ -217,6 +304,14 @@ fn test_fn_spc() {
     assert_eq!(exec(&mut r), " \n");
     r.enter(r#"?spc(256)"#);
     assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"?spc(300)"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"PRINT "A"; SPC(3); "B""#);
+    assert_eq!(exec(&mut r), "A   B\n");
+    r.enter(r#"PRINT "A";SPC(0);"B""#);
+    assert_eq!(exec(&mut r), "AB\n");
+    r.enter(r#"PRINT "A"; SPC(3); POS(0)"#);
+    assert_eq!(exec(&mut r), "A    4 \n");
 }
 
 #[test]
@@ -224,6 +319,12 @@ fn test_fn_sqr() {
     let mut r = Runtime::default();
     r.enter(r#"?sqr(5)"#);
     assert_eq!(exec(&mut r), " 2.236068 \n");
+    r.enter(r#"?sqr(0)"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+    r.enter(r#"?sqr(-1)"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    r.enter(r#"?sqr(-0.5)"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
 }
 
 #[test]
@@ -238,8 +339,13 @@ fn test_fn_string() {
     let mut r = Runtime::default();
     r.enter(r#"?string$(5,45)"#);
     assert_eq!(exec(&mut r), "-----\n");
-    r.enter(r#"?string$(3,"^*")"#);
+    r.enter(r#"?string$(3,"^")"#);
     assert_eq!(exec(&mut r), "^^^\n");
+    r.enter(r#"?string$(3,"AB")"#);
+    assert_eq!(
+        exec(&mut r),
+        "?ILLEGAL FUNCTION CALL; EXPECTED SINGLE CHAR\n"
+    );
     r.enter(r#"?string$(3,"")"#);
     assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
     r.enter(r#"?string$(256,"=")"#);
@@ -264,6 +370,33 @@ fn test_fn_tan() {
     assert_eq!(exec(&mut r), " 0.40477434 \n");
 }
 
+#[test]
+fn test_fn_time() {
+    let mut r = Runtime::default();
+    r.set_clock(Box::new(FixedClock));
+    r.enter(r#"?time$"#);
+    assert_eq!(exec(&mut r), "09:05:30\n");
+}
+
+#[test]
+fn test_set_time_and_date() {
+    let mut r = Runtime::default();
+    r.set_clock(Box::new(FixedClock));
+    r.enter(r#"TIME$="12:00:00":?TIME$;" ";DATE$"#);
+    assert_eq!(exec(&mut r), "12:00:00 08-12-1981\n");
+    r.enter(r#"DATE$="01/02/2000":?TIME$;" ";DATE$"#);
+    assert_eq!(exec(&mut r), "12:00:00 01-02-2000\n");
+    r.enter(r#"TIME$="23:59":?TIME$"#);
+    assert_eq!(exec(&mut r), "23:59:00\n");
+    r.enter(r#"TIME$="25:00:00""#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    r.enter(r#"DATE$=1"#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH\n");
+    r.set_clock(Box::new(FixedClock));
+    r.enter(r#"?TIME$"#);
+    assert_eq!(exec(&mut r), "09:05:30\n");
+}
+
 #[test]
 fn test_fn_val() {
     let mut r = Runtime::default();
@@ -282,3 +415,31 @@ fn test_fn_val() {
     r.enter(r#"?val("1")/3"#);
     assert_eq!(exec(&mut r), " 0.3333333333333333 \n");
 }
+
+#[test]
+fn test_register_function() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 PRINT CUBE(3)"#);
+    r.register_function(
+        "cube",
+        1..=1,
+        Box::new(|args| {
+            let n = f64::try_from(args[0].clone())?;
+            Ok(Val::Double(n * n * n))
+        }),
+    )
+    .unwrap();
+    r.enter(r#"20 PRINT CUBE("X")"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 27 \n?TYPE MISMATCH IN 20:15\n");
+    r.enter(r#"?CUBE(1,2)"#);
+    assert_eq!(
+        exec(&mut r),
+        "?ILLEGAL FUNCTION CALL; WRONG NUMBER OF ARGUMENTS\n"
+    );
+    r.enter(r#"CUBE=2:?CUBE;CUBE(CUBE)"#);
+    assert_eq!(exec(&mut r), " 2  8 \n");
+    assert!(r
+        .register_function("LEN", 1..=1, Box::new(|args| Ok(args[0].clone())))
+        .is_err());
+}
@@ -45,6 +45,28 @@ fn test_go_sub_2() {
     assert_eq!(token("GO SUB"), Some(Token::Word(Word::Gosub)));
 }
 
+#[test]
+fn test_goto_no_space() {
+    let (_, v) = lex("10 GOTO100");
+    let mut x = v.iter();
+    assert_eq!(x.next(), Some(&Token::Word(Word::Goto)));
+    assert_eq!(x.next(), Some(&Token::Whitespace(1)));
+    assert_eq!(
+        x.next(),
+        Some(&Token::Literal(Literal::Integer("100".to_string())))
+    );
+    assert_eq!(x.next(), None);
+    let (_, v) = lex("10 gosub200");
+    let mut x = v.iter();
+    assert_eq!(x.next(), Some(&Token::Word(Word::Gosub)));
+    assert_eq!(x.next(), Some(&Token::Whitespace(1)));
+    assert_eq!(
+        x.next(),
+        Some(&Token::Literal(Literal::Integer("200".to_string())))
+    );
+    assert_eq!(x.next(), None);
+}
+
 #[test]
 fn test_print_1() {
     let (ln, v) = lex("10 ?");
@@ -83,6 +105,36 @@ fn test_numbers() {
     );
 }
 
+#[test]
+fn test_abbreviations() {
+    let l = Line::new(r#"10 p."HI":gos.200"#);
+    assert_eq!(&l.to_string(), r#"10 PRINT "HI":GOSUB 200"#);
+    let l = Line::new("10 IF A THEN P.1 ELSE G.20");
+    assert_eq!(&l.to_string(), "10 IF A THEN PRINT 1 ELSE GOTO 20");
+    let l = Line::new("10 FOR I=1 TO 2:N.I");
+    assert_eq!(&l.to_string(), "10 FOR I=1 TO 2:NEXT I");
+    let l = Line::new("10 X=P.");
+    assert_eq!(&l.to_string(), "10 X=P .");
+}
+
+#[test]
+fn test_abbreviations_all() {
+    let l = Line::new(r#"10 CON.:DI.A(5):GOS.100:LE.A=1:LO."X":PR.1:REA.A:RES.:RET.:RU."#);
+    assert_eq!(
+        &l.to_string(),
+        r#"10 CONT:DIM A(5):GOSUB 100:LET A=1:LOAD "X":PRINT 1:READ A:RESTORE:RETURN:RUN"#
+    );
+    let l = Line::new(r#"10 D.1:E.:F.I=1 TO 2:G.10:I.A:L.:N.:P.2:S."X""#);
+    assert_eq!(
+        &l.to_string(),
+        r#"10 DATA 1:END:FOR I=1 TO 2:GOTO 10:INPUT A:LIST:NEXT:PRINT 2:SAVE "X""#
+    );
+    let l = Line::new("CON.");
+    assert_eq!(&l.to_string(), "CONT");
+    let l = Line::new("10 X=CON.");
+    assert_eq!(&l.to_string(), "10 X=C ON .");
+}
+
 #[test]
 fn test_annotated_numbers() {
     assert_eq!(
@@ -99,6 +151,22 @@ fn test_annotated_numbers() {
     );
 }
 
+#[test]
+fn test_radix_numbers() {
+    assert_eq!(
+        token("&H1F"),
+        Some(Token::Literal(Literal::Hex("1F".to_string())))
+    );
+    assert_eq!(
+        token("&17"),
+        Some(Token::Literal(Literal::Octal("17".to_string())))
+    );
+    assert_eq!(
+        token("&b1010"),
+        Some(Token::Literal(Literal::Binary("1010".to_string())))
+    );
+}
+
 #[test]
 fn test_remark1() {
     let (ln, v) = lex("100 REM  A fortunate comment");
@@ -123,6 +191,23 @@ fn test_remark2() {
     assert_eq!(x.next(), None);
 }
 
+#[test]
+fn test_remark_round_trip() {
+    for s in [
+        "10 REM hello",
+        "10 ' a:b \"c\" & %",
+        "10 PRINT 1:REM x: \"y",
+        "10 PRINT 1 ' two  spaces",
+        "10 REMark",
+    ] {
+        assert_eq!(Line::new(s).to_string(), s);
+    }
+    assert_eq!(
+        Line::new("10 rem trailing  ").to_string(),
+        "10 REM trailing"
+    );
+}
+
 #[test]
 fn test_ident_with_word() {
     let (ln, v) = lex("BANDS");
@@ -136,6 +221,23 @@ fn test_ident_with_word() {
     assert_eq!(x.next(), None);
 }
 
+#[test]
+fn test_ident_with_new_word() {
+    let (_, v) = lex("DOT=LOOPS+UNTILX");
+    let mut x = v.iter();
+    assert_eq!(x.next(), Some(&Token::Ident(Ident::Plain("DOT".into()))));
+    assert_eq!(x.next(), Some(&Token::Operator(Operator::Equal)));
+    assert_eq!(x.next(), Some(&Token::Ident(Ident::Plain("LOOPS".into()))));
+    assert_eq!(x.next(), Some(&Token::Operator(Operator::Plus)));
+    assert_eq!(x.next(), Some(&Token::Ident(Ident::Plain("UNTILX".into()))));
+    assert_eq!(x.next(), None);
+    assert_eq!(token("DO"), Some(Token::Word(Word::Do)));
+    assert_eq!(token("LOOP"), Some(Token::Word(Word::Loop)));
+    assert_eq!(token("UNTIL"), Some(Token::Word(Word::Until)));
+    let (_, v) = lex("IF X THENDO");
+    assert_eq!(v.last(), Some(&Token::Word(Word::Do)));
+}
+
 #[test]
 fn test_for_loop() {
     let (ln, v) = lex("forI%=1to30");
@@ -241,6 +343,38 @@ fn test_direct() {
     assert_eq!(&l.to_string(), "RUN");
 }
 
+#[test]
+fn test_tokens_with_spans() {
+    let l = Line::new("10 print a");
+    assert_eq!(
+        l.tokens_with_spans(),
+        vec![
+            (Token::Word(Word::Print), 3..8),
+            (Token::Whitespace(1), 8..9),
+            (Token::Ident(Ident::Plain("A".to_string())), 9..10),
+        ]
+    );
+    let l = Line::new("?\"HI\"");
+    assert_eq!(
+        l.tokens_with_spans(),
+        vec![
+            (Token::Word(Word::Print), 0..5),
+            (Token::Whitespace(1), 5..6),
+            (Token::Literal(Literal::String("HI".to_string())), 6..10),
+        ]
+    );
+}
+
+#[test]
+fn test_lex_errors() {
+    let l = Line::new("10 PRINT @");
+    assert_eq!(l.lex_errors(), vec![(9..10, "@".to_string())]);
+    let l = Line::new("10 PRINT \"@\" ' @");
+    assert_eq!(l.lex_errors(), vec![]);
+    let l = Line::new("A=1 @ B");
+    assert_eq!(l.lex_errors(), vec![(4..5, "@".to_string())]);
+}
+
 #[test]
 fn test_indirect() {
     let l = Line::new("100 end");
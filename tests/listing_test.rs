@@ -0,0 +1,161 @@
+use basic::lang::Line;
+use basic::mach::{Listing, PatchAction};
+
+#[test]
+fn test_apply_patch_manifest() {
+    let mut listing = Listing::default();
+    let actions = listing.apply_patch(
+        r#"' Hunt the Wumpus.
+"wumpus.bas" 1179EE56 http://example.com/wumpus.bas
+0 ' https://en.wikipedia.org/wiki/Hunt_the_Wumpus
+"notes.bas"
+10 PRINT "HELLO"
+"#,
+    );
+    assert_eq!(
+        actions.unwrap(),
+        vec![
+            PatchAction::Message("Hunt the Wumpus.".into()),
+            PatchAction::Retrieve {
+                index: 1,
+                filename: "wumpus.bas".into(),
+                crc: 0x1179EE56,
+                url: "http://example.com/wumpus.bas".into(),
+                lines: vec![(
+                    2,
+                    "0 ' https://en.wikipedia.org/wiki/Hunt_the_Wumpus".into()
+                )],
+            },
+            PatchAction::Create {
+                index: 3,
+                filename: "notes.bas".into(),
+                lines: vec![(4, r#"10 PRINT "HELLO""#.into())],
+            },
+        ]
+    );
+    assert!(listing.is_empty());
+}
+
+#[test]
+fn test_apply_patch_errors() {
+    let mut listing = Listing::default();
+    let error = listing.apply_patch(r#""foo.bas" XYZ http://example.com"#);
+    assert_eq!(
+        error.unwrap_err().to_string(),
+        "?SYNTAX ERROR; Unable to parse crc info in line 1 of the patch file."
+    );
+    let error = listing.apply_patch("\"\" 0 http://example.com");
+    assert_eq!(
+        error.unwrap_err().to_string(),
+        "?BAD FILE NAME; In line 1 of the patch file."
+    );
+}
+
+#[test]
+fn test_apply_patch_program() {
+    let mut listing = Listing::default();
+    let actions = listing.apply_patch("10 PRINT 1\n20 END\n").unwrap();
+    assert!(actions.is_empty());
+    assert_eq!(listing.lines().count(), 2);
+    let error = listing.apply_patch("PRINT 1\n").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "?DIRECT STATEMENT IN FILE; In line 1 of the file."
+    );
+}
+
+#[test]
+fn test_apply_patch_continuation() {
+    let mut listing = Listing::default();
+    listing
+        .apply_patch("10 PRINT 1; _\n  2; _\n3\n20 PRINT \"A _\"\n30 PRINT \"B\" _\n")
+        .unwrap();
+    let lines: Vec<String> = listing.lines().map(|l| l.to_string()).collect();
+    assert_eq!(
+        lines,
+        vec!["10 PRINT 1;   2; 3", "20 PRINT \"A _\"", "30 PRINT \"B\""]
+    );
+    assert_eq!(Listing::continuation("10 PRINT \"A _"), None);
+    assert_eq!(Listing::continuation("10 A_"), None);
+    assert_eq!(Listing::continuation("10 A _  "), Some("10 A "));
+    assert_eq!(
+        Listing::logical_lines("10 A _\n B\n\n20 C _\n"),
+        vec![
+            (1, "10 A  B".to_string()),
+            (2, "".to_string()),
+            (3, "20 C ".to_string())
+        ]
+    );
+    let error = Listing::default()
+        .apply_patch("10 END\nPRINT _\n")
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "?DIRECT STATEMENT IN FILE; In line 2 of the file."
+    );
+}
+
+#[test]
+fn test_patch_crc() {
+    let mut listing = Listing::default();
+    listing.apply_patch("10 PRINT 1\n").unwrap();
+    let crc = listing.crc32();
+    let lines = vec![(3, "20 END".to_string())];
+    let error = listing.patch(crc ^ 1, 2, &lines).unwrap_err();
+    assert!(error.to_string().starts_with("?SYNTAX ERROR; Expected CRC"));
+    assert!(error.to_string().ends_with(" in line 3 of the patch file."));
+    listing.patch(crc, 2, &lines).unwrap();
+    assert_eq!(listing.lines().count(), 2);
+    let error = listing
+        .patch(listing.crc32(), 2, &[(5, "PRINT 2".into())])
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "?DIRECT STATEMENT IN FILE; In line 6 of the patch file."
+    );
+}
+
+#[test]
+fn test_crc32_stable() {
+    let mut listing = Listing::default();
+    listing.load_str(r#"10 print "HELLO""#).unwrap();
+    listing.load_str("20 end").unwrap();
+    assert_eq!(listing.crc32(), 0x1BD29E56);
+}
+
+#[test]
+fn test_insert_out_of_order() {
+    let mut listing = Listing::default();
+    for s in ["30 END", "10 PRINT 1", "20 PRINT 2"] {
+        listing.insert(Line::new(s));
+    }
+    let numbers: Vec<_> = listing.lines().map(|line| line.number()).collect();
+    assert_eq!(numbers, vec![Some(10), Some(20), Some(30)]);
+    assert_eq!(listing.line(20), Some(("20 PRINT 2".to_string(), vec![])));
+    assert_eq!(listing.line(15), None);
+    assert_eq!(listing.line(99999), None);
+}
+
+#[test]
+fn test_renumber_preview() {
+    let mut listing = Listing::default();
+    for s in ["10 GOTO 30", "20 PRINT", "30 END"] {
+        listing.insert(Line::new(s));
+    }
+    assert_eq!(
+        listing.renumber_preview(100, 0, 10).unwrap(),
+        vec![(10, 100), (20, 110), (30, 120)]
+    );
+    assert_eq!(
+        listing.renumber_preview(5, 20, 5).unwrap_err().to_string(),
+        "?ILLEGAL FUNCTION CALL"
+    );
+    assert_eq!(
+        listing
+            .renumber_preview(65000, 0, 1000)
+            .unwrap_err()
+            .to_string(),
+        "?OVERFLOW"
+    );
+    assert_eq!(listing.line(10), Some(("10 GOTO 30".to_string(), vec![])));
+}
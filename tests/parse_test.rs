@@ -1,4 +1,4 @@
-use basic::lang::{ast::*, lex, parse};
+use basic::lang::{ast::*, lex, parse, parse_all};
 
 fn parse_str(s: &str) -> Option<Statement> {
     let (lin, tokens) = lex(s);
@@ -167,3 +167,46 @@ fn test_remarks() {
         )))
     );
 }
+
+#[test]
+fn test_parse_all() {
+    let (lin, tokens) = lex("10 A=:PRINT 1:B=2 3:END");
+    let (statements, errors) = parse_all(lin, &tokens);
+    assert_eq!(statements.len(), 3);
+    assert!(matches!(statements[0], Statement::Print(..)));
+    assert!(matches!(statements[1], Statement::Let(..)));
+    assert!(matches!(statements[2], Statement::End(..)));
+    let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(
+        errors,
+        vec![
+            "?SYNTAX ERROR IN 10:6; EXPECTED EXPRESSION",
+            "?SYNTAX ERROR IN 10:19; UNEXPECTED TOKEN"
+        ]
+    );
+}
+
+#[test]
+fn test_cls_mode() {
+    assert_eq!(
+        parse_str("CLS"),
+        Some(Statement::Cls(0..3, Expression::Integer(0..3, 0)))
+    );
+    assert_eq!(
+        parse_str("CLS 2"),
+        Some(Statement::Cls(0..3, Expression::Integer(4..5, 2)))
+    );
+}
+
+#[test]
+fn test_to_sexpr() {
+    let (lin, tokens) = lex("10 PRINT 1+2");
+    let statements = parse(lin, &tokens).unwrap();
+    assert_eq!(to_sexpr(&statements), r#"(print (add 1 2) "\n")"#);
+    let (lin, tokens) = lex(r#"10 IF A(I)<-1 THEN B$="X" ELSE 20"#);
+    let statements = parse(lin, &tokens).unwrap();
+    assert_eq!(
+        to_sexpr(&statements),
+        r#"(if (lt (A I) (neg 1)) (then (let B$ "X")) (else (goto 20)))"#
+    );
+}
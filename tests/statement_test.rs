@@ -1,6 +1,11 @@
 mod common;
-use basic::mach::Runtime;
+use basic::lang::token::{Literal, Token, Word};
+use basic::lang::{Error, Line};
+use basic::mach::{EnterResult, Event, FileSystem, Frame, Program, Runtime, Val};
 use common::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 #[test]
 fn test_indirect_error() {
@@ -10,6 +15,58 @@ fn test_indirect_error() {
     assert_eq!(exec(&mut r), "?UNDEFINED LINE IN 10:9\n");
 }
 
+#[test]
+fn test_auto_print() {
+    let mut r = Runtime::default();
+    r.enter(r#"A=5"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"A*2"#);
+    assert_eq!(exec(&mut r), "?SYNTAX ERROR; UNKNOWN STATEMENT\n");
+    r.set_auto_print(true);
+    r.enter(r#"1+2"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+    r.enter(r#"A*2;"X""#);
+    assert_eq!(exec(&mut r), " 10 X\n");
+    r.enter(r#"A=7"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"10 A=A+1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"A"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"20 +5"#);
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 A=A+1\n20 +5\n");
+}
+
+#[test]
+fn test_banner() {
+    let mut r = Runtime::default();
+    r.set_banner(Some("MY BASIC".into()));
+    assert!(matches!(r.execute(5000), Event::Print(s) if s == "MY BASIC\n"));
+    assert!(matches!(r.execute(5000), Event::Print(s) if s == "READY.\n"));
+    let mut r = Runtime::default();
+    r.set_banner(Some("".into()));
+    assert!(matches!(r.execute(5000), Event::Print(s) if s == "READY.\n"));
+    assert!(matches!(r.execute(5000), Event::Stopped));
+    let mut r = Runtime::default();
+    r.set_banner(None);
+    assert!(matches!(r.execute(5000), Event::Print(s) if s.starts_with("64K BASIC")));
+}
+
+#[test]
+fn test_cls_mode() {
+    let mut r = Runtime::default();
+    r.enter(r#"CLS"#);
+    assert!(matches!(r.execute(5000), Event::Cls(0)));
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"CLS 2"#);
+    assert!(matches!(r.execute(5000), Event::Cls(2)));
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"CLS 3"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+}
+
 #[test]
 fn test_cont_after_end() {
     let mut r = Runtime::default();
@@ -39,6 +96,29 @@ fn test_cont_after_stop() {
     assert_eq!(exec(&mut r), " 1 \n");
 }
 
+#[test]
+fn test_cont_after_error() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 A=1"#);
+    r.enter(r#"20 A%=40000"#);
+    r.enter(r#"30 PRINT A"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW IN 20\n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW IN 20\n");
+    r.enter(r#"A%=40000"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW\n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), "?CAN'T CONTINUE\n");
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OVERFLOW IN 20\n");
+    r.enter(r#"20 A%=4"#);
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), "?CAN'T CONTINUE\n");
+}
+
 #[test]
 fn test_cont_after_debug() {
     let mut r = Runtime::default();
@@ -53,6 +133,26 @@ fn test_cont_after_debug() {
     assert_eq!(exec(&mut r), " 2 \n");
 }
 
+#[test]
+fn test_end_exit_code() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 PRINT "BYE":END 2"#);
+    r.enter(r#"RUN"#);
+    let mut code = None;
+    for _ in 0..10 {
+        if let Event::Exit(n) = r.execute(5000) {
+            code = Some(n);
+        }
+    }
+    assert_eq!(code, Some(2));
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "BYE\n");
+    r.enter(r#"END 1:PRINT "NOT HERE""#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"END "X""#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH\n");
+}
+
 #[test]
 fn test_end_then_cont() {
     let mut r = Runtime::default();
@@ -95,6 +195,69 @@ fn test_dim() {
     assert_eq!(exec(&mut r), " 0  9 \n");
     r.enter(r#"z(10)=100:?Z(1);Z(10)"#);
     assert_eq!(exec(&mut r), " 0  100 \n");
+    r.enter(r#"DIM Y(1000,1000,1000)"#);
+    assert_eq!(exec(&mut r), "?OUT OF MEMORY; ARRAY TOO LARGE\n");
+    r.enter(r#"DIM Y(180,180):Y(180,180)=1:?Y(180,180)"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"DIM W(181,181)"#);
+    assert_eq!(exec(&mut r), "?OUT OF MEMORY; ARRAY TOO LARGE\n");
+}
+
+#[test]
+fn test_dim_strict_arrays() {
+    let mut r = Runtime::default();
+    r.enter(r#"A(3)=1:?A(3)"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"CLEAR"#);
+    assert_eq!(exec(&mut r), "");
+    r.set_strict_arrays(true);
+    r.enter(r#"A(3)=1"#);
+    assert_eq!(
+        exec(&mut r),
+        "?SUBSCRIPT OUT OF RANGE; ARRAY NOT DIMENSIONED\n"
+    );
+    r.enter(r#"?B(3)"#);
+    assert_eq!(
+        exec(&mut r),
+        "?SUBSCRIPT OUT OF RANGE; ARRAY NOT DIMENSIONED\n"
+    );
+    r.enter(r#"DIM A(5):A(3)=1:?A(3)"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+}
+
+#[test]
+fn test_dim_unassigned() {
+    let mut r = Runtime::default();
+    r.enter(r#"DIM A$(5):PRINT A$(3)"#);
+    assert_eq!(exec(&mut r), "\n");
+    r.enter(r#"DIM A(5):PRINT A(3)"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+}
+
+#[test]
+fn test_dim_expression_bounds() {
+    let mut r = Runtime::default();
+    r.enter(r#"N=5:DIM A(N):A(5)=1:PRINT A(5)"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"N=-1:DIM B(N)"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; B(-1)\n");
+    r.enter(r#"N=2:DIM C(N*2,N+1):C(4,3)=7:PRINT C(4,3)"#);
+    assert_eq!(exec(&mut r), " 7 \n");
+    r.enter(r#"PRINT C(5,3)"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; C(5,3)\n");
+}
+
+#[test]
+fn test_subscript_out_of_range() {
+    let mut r = Runtime::default();
+    r.enter(r#"?A(-1)"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; A(-1)\n");
+    r.enter(r#"A$(100)="X""#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; A$(100)\n");
+    r.enter(r#"DIM B(5,5):?B(2,6)"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; B(2,6)\n");
+    r.enter(r#"?B(1)"#);
+    assert_eq!(exec(&mut r), "?SUBSCRIPT OUT OF RANGE; B(1)\n");
 }
 
 #[test]
@@ -107,6 +270,34 @@ fn test_def_fn() {
     assert_eq!(exec(&mut r), " 0.6666667 \n");
 }
 
+#[test]
+fn test_def_fn_name() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DEF LEN(X)=X"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?SYNTAX ERROR IN 10:8; MUST START WITH FN\n");
+    r.enter(r#"10 DEF FNSIN(X)=X*2"#);
+    r.enter(r#"20 PRINT FNSIN(3);SIN(0)"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 6  0 \n");
+}
+
+#[test]
+fn test_delete() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 PRINT 1"#);
+    r.enter(r#"20 PRINT 2"#);
+    r.enter(r#"30 PRINT 3"#);
+    r.enter(r#"DELETE 20"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 PRINT 1\n30 PRINT 3\n");
+    r.enter(r#"DELETE"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 PRINT 1\n30 PRINT 3\n");
+}
+
 #[test]
 fn test_deftype() {
     let mut r = Runtime::default();
@@ -130,6 +321,21 @@ fn test_deftype() {
     assert_eq!(exec(&mut r), " 1 \n");
 }
 
+#[test]
+fn test_enter_ex() {
+    let mut r = Runtime::default();
+    assert_eq!(r.enter_ex(r#"10 INPUT A"#), EnterResult::Edited);
+    assert_eq!(r.enter_ex(r#"20 PRINT A"#), EnterResult::Edited);
+    assert_eq!(r.enter_ex(r#""#), EnterResult::Ignored);
+    assert_eq!(r.enter_ex(r#"RUN"#), EnterResult::Ran);
+    assert_eq!(exec(&mut r), "? ");
+    assert_eq!(r.enter_ex(r#"42"#), EnterResult::AwaitingInput);
+    assert_eq!(exec(&mut r), " 42 \n");
+    assert!(!r.enter(r#"20"#));
+    assert!(r.enter(r#"LIST"#));
+    assert_eq!(exec(&mut r), "10 INPUT A\n");
+}
+
 #[test]
 fn test_erase() {
     let mut r = Runtime::default();
@@ -143,6 +349,22 @@ fn test_erase() {
     assert_eq!(exec(&mut r), "\n");
 }
 
+#[test]
+fn test_erase_array_len() {
+    let mut r = Runtime::default();
+    assert_eq!(r.array_len("A"), None);
+    r.enter(r#"DIM A(3,4):A(3,4)=9:B$(2)="X""#);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(r.array_len("A"), Some(20));
+    assert_eq!(r.array_len("B$"), Some(11));
+    r.enter(r#"ERASE A"#);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(r.array_len("A"), None);
+    r.enter(r#"DIM A(3,4):PRINT A(3,4)"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+    assert_eq!(r.array_len("A"), Some(20));
+}
+
 #[test]
 fn test_for_loop_break_with_goto() {
     let mut r = Runtime::default();
@@ -156,6 +378,59 @@ fn test_for_loop_break_with_goto() {
     assert_eq!(exec(&mut r), " 1  8 \n 2  8 \n");
 }
 
+#[test]
+fn test_exit_for() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 FOR I=1 TO 3"#);
+    r.enter(r#"20 FOR J=1 TO 9"#);
+    r.enter(r#"30 IF J>I THEN EXIT FOR"#);
+    r.enter(r#"40 PRINT I*10+J;"#);
+    r.enter(r#"50 NEXT J,I"#);
+    r.enter(r#"60 FOR K=1 TO 9:EXIT FOR:NEXT:PRINT K"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 11  21  22  31  32  33  1 \n");
+    r.enter(r#"NEXT"#);
+    assert_eq!(exec(&mut r), "?NEXT WITHOUT FOR\n");
+}
+
+#[test]
+fn test_exit_names() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 EXITS=1:PRINT EXITS"#);
+    r.enter(r#"20 FOR I=1 TO 9:EXITFOR:NEXT:PRINT I"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n 1 \n");
+}
+
+#[test]
+fn test_exit_for_errors() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 EXIT FOR"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?NEXT WITHOUT FOR IN 10:4\n");
+    r.enter(r#"10 FOR I=1 TO 2:EXIT FOR"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?FOR WITHOUT NEXT IN 10:17\n");
+    r.enter(r#"10 EXIT DO"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(
+        exec(&mut r),
+        "?SYNTAX ERROR IN 10:9; EXPECTED FOR OR WHILE\n"
+    );
+}
+
+#[test]
+fn test_for_loop_execute_counted() {
+    let mut r = Runtime::default();
+    r.execute(1);
+    r.enter(r#"FOR I=1 TO 10:NEXT"#);
+    let (_event, count) = r.execute_counted(5000);
+    assert_eq!(count, 17);
+    r.enter(r#"FOR I=1 TO 10:NEXT"#);
+    let (_event, count) = r.execute_counted(5);
+    assert_eq!(count, 5);
+}
+
 #[test]
 fn test_for_loop_always_runs_once() {
     let mut r = Runtime::default();
@@ -182,6 +457,78 @@ fn test_gosub_return() {
     assert_eq!(exec(&mut r), "HELLO WORLD\n");
 }
 
+#[test]
+fn test_goto_forward_reference() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOTO 30"#);
+    r.enter(r#"20 PRINT "NO""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?UNDEFINED LINE IN 10:9\n");
+    r.enter(r#"30 PRINT "YES""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "YES\n");
+    r.enter(r#"40 GOSUB 50:END"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?UNDEFINED LINE IN 40:10\n");
+    r.enter(r#"50 PRINT "SUB":RETURN"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "YES\nSUB\n");
+    r.enter(r#"GOTO 60"#);
+    assert_eq!(exec(&mut r), "?UNDEFINED LINE\n");
+}
+
+#[test]
+fn test_goto_undefined_lines() {
+    let lines: Vec<Line> = ["10 GOTO 100", "20 PRINT", "30 IF 1 THEN 200 ELSE 20"]
+        .iter()
+        .map(|s| Line::new(s))
+        .collect();
+    let mut program = Program::default();
+    program.codegen(&lines);
+    program.link();
+    assert_eq!(program.undefined_lines(), vec![(10, 100), (30, 200)]);
+}
+
+#[test]
+fn test_program_bytecode_round_trip() {
+    let lines: Vec<Line> = [
+        r#"10 READ A$,B:GOSUB 40"#,
+        r#"20 FOR I=1 TO B:PRINT A$;I:NEXT"#,
+        r#"30 PRINT CHR$(-1):END"#,
+        r#"40 PRINT "SUB";B*1.5:RETURN"#,
+        r#"50 DATA "HI",2"#,
+    ]
+    .iter()
+    .map(|s| Line::new(s))
+    .collect();
+    let mut program = Program::default();
+    program.codegen(&lines);
+    program.link();
+    let bytes = program.serialize();
+    let mut r = Runtime::default();
+    r.set_program(Program::deserialize(&bytes).unwrap());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "SUB 3 \nHI 1 \nHI 2 \n?OVERFLOW IN 30:15\n");
+    assert!(Program::deserialize(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Program::deserialize(b"10 PRINT").is_err());
+}
+
+#[test]
+fn test_goto_no_space() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOSUB30:GOTO40"#);
+    r.enter(r#"30 PRINT 3:RETURN"#);
+    r.enter(r#"40 PRINT 4"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 3 \n 4 \n");
+    r.enter(r#"10 GOTOA"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(
+        exec(&mut r),
+        "?SYNTAX ERROR IN 10:9; EXPECTED LINE NUMBER\n"
+    );
+}
+
 #[test]
 fn test_if_then() {
     let mut r = Runtime::default();
@@ -200,6 +547,19 @@ fn test_if_then_else() {
     assert_eq!(exec(&mut r), "one 2 \n");
     r.enter(r#"if 0 then ? "one";:?2"#);
     assert_eq!(exec(&mut r), "");
+    r.enter(r#"IF 1 THEN ?1:?2 ELSE ?3"#);
+    assert_eq!(exec(&mut r), " 1 \n 2 \n");
+    r.enter(r#"IF 0 THEN ?1:?2 ELSE ?3"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+    r.enter(r#"IF 1 THEN IF 0 THEN ?1 ELSE ?2:?5 ELSE ?3"#);
+    assert_eq!(exec(&mut r), " 2 \n 5 \n");
+    r.enter(r#"IF 1 THEN?"yes""#);
+    assert_eq!(exec(&mut r), "yes\n");
+    r.enter(r#"IF 0 THEN?1ELSE?2"#);
+    assert_eq!(exec(&mut r), " 2 \n");
+    r.enter(r#"10 IF 1 THEN ? 1 ELSE ? 2"#);
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 IF 1 THEN PRINT 1 ELSE PRINT 2\n");
 }
 
 #[test]
@@ -209,6 +569,162 @@ fn test_input_to_array() {
     assert_eq!(exec(&mut r), "? ");
     r.enter(r#"1,2"#);
     assert_eq!(exec(&mut r), " 1  2 \n");
+    r.enter(r#"INPUT I, A(I)"#);
+    assert_eq!(exec(&mut r), "? ");
+    r.enter(r#"2,7"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT A(2);A(0)"#);
+    assert_eq!(exec(&mut r), " 7  0 \n");
+}
+
+#[test]
+fn test_input_cancel() {
+    let mut r = Runtime::default();
+    assert!(!r.cancel_input());
+    r.enter(r#"INPUT A:PRINT A"#);
+    assert_eq!(exec(&mut r), "? ");
+    assert!(r.cancel_input());
+    assert_eq!(exec(&mut r), "?BREAK\n");
+    r.enter(r#"PRINT 5"#);
+    assert_eq!(exec(&mut r), " 5 \n");
+    r.enter(r#"10 INPUT A:PRINT A"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "? ");
+    assert!(r.cancel_input());
+    assert_eq!(exec(&mut r), "?BREAK IN 10\n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), "? ");
+    r.enter(r#"3"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+}
+
+#[test]
+fn test_input_no_newline() {
+    fn input(r: &mut Runtime) -> (String, bool, bool) {
+        loop {
+            match r.execute(5000) {
+                Event::Input(prompt, caps, _, newline) => return (prompt, caps, newline),
+                Event::Running => {}
+                event => panic!("{:?}", event),
+            }
+        }
+    }
+    let mut r = Runtime::default();
+    r.enter(r#"INPUT ;A:PRINT "X";POS(0)"#);
+    assert_eq!(input(&mut r), ("? ".into(), true, false));
+    r.enter(r#"12"#);
+    assert_eq!(exec(&mut r), "X 5 \n");
+    r.enter(r#"INPUT;,"N";A$:PRINT POS(0)"#);
+    assert_eq!(input(&mut r), ("N? ".into(), false, false));
+    r.enter(r#"ab"#);
+    assert_eq!(exec(&mut r), " 5 \n");
+    r.enter(r#"INPUT A:PRINT POS(0)"#);
+    assert_eq!(input(&mut r), ("? ".into(), true, true));
+    r.enter(r#"1"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+}
+
+#[test]
+fn test_input_redo_prefill() {
+    fn input_redo(r: &mut Runtime) -> String {
+        loop {
+            match r.execute(5000) {
+                Event::Input(_, _, s, _) => return s,
+                Event::Running | Event::Errors(_) | Event::Print(_) => {}
+                event => panic!("{:?}", event),
+            }
+        }
+    }
+    let mut r = Runtime::default();
+    r.enter(r#"input a,b:print a;b"#);
+    assert_eq!(input_redo(&mut r), "");
+    r.enter(r#"1,x"#);
+    assert_eq!(input_redo(&mut r), "1,x");
+    r.enter(r#"1"#);
+    assert_eq!(input_redo(&mut r), "1");
+    r.enter(r#"1,2"#);
+    assert_eq!(exec(&mut r), " 1  2 \n");
+    r.enter(r#"input a"#);
+    assert_eq!(input_redo(&mut r), "");
+}
+
+#[test]
+fn test_input_validator() {
+    let mut r = Runtime::default();
+    r.set_input_validator(Some(Box::new(|s: &str| {
+        if s.trim().is_empty() {
+            Err("BLANK".to_string())
+        } else {
+            Ok(s.to_uppercase())
+        }
+    })));
+    r.enter(r#"INPUT A$:PRINT A$"#);
+    assert_eq!(exec(&mut r), "? ");
+    r.enter(r#"  "#);
+    assert_eq!(exec(&mut r), "?REDO FROM START; BLANK\n? ");
+    r.enter(r#"abc"#);
+    assert_eq!(exec(&mut r), "ABC\n");
+}
+
+#[test]
+fn test_is_set() {
+    let mut r = Runtime::default();
+    r.enter(r#"A=0:C$="":D%=1"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.is_set("A"));
+    assert!(!r.is_set("B"));
+    assert!(r.is_set("C$"));
+    assert!(r.is_set("D%"));
+    r.enter(r#"PRINT B"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+    assert!(!r.is_set("B"));
+    r.enter(r#"CLEAR"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(!r.is_set("A"));
+}
+
+#[test]
+fn test_clear_variable() {
+    let mut r = Runtime::default();
+    r.enter(r#"A=1:B=2:A$="X""#);
+    assert_eq!(exec(&mut r), "");
+    r.clear_variable("A");
+    assert!(!r.is_set("A"));
+    assert!(r.is_set("B"));
+    assert!(r.is_set("A$"));
+    r.enter(r#"PRINT A;B;A$"#);
+    assert_eq!(exec(&mut r), " 0  2 X\n");
+}
+
+#[test]
+fn test_retain_zero() {
+    let mut r = Runtime::default();
+    r.enter(r#"A=0:B$="":C=1"#);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(r.variable_count(), 1);
+    r.set_retain_zero(true);
+    r.enter(r#"A=0:B$="":C=0"#);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(r.variable_count(), 3);
+    r.enter(r#"PRINT A;B$;C"#);
+    assert_eq!(exec(&mut r), " 0  0 \n");
+}
+
+#[test]
+fn test_set_variable() {
+    let mut r = Runtime::default();
+    r.set_variable("n%", Val::Single(5.7)).unwrap();
+    r.set_variable("A$", Val::String("ARG".into())).unwrap();
+    assert!(r.set_variable("A$", Val::Integer(1)).is_err());
+    assert!(r.set_variable("A(1)", Val::Integer(1)).is_err());
+    r.enter(r#"10 PRINT N%;A$:N%=0"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 5 ARG\n");
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 5 ARG\n");
+    r.clear_variable("N%");
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 0 ARG\n");
 }
 
 #[test]
@@ -230,6 +746,102 @@ fn test_let_mid_statement() {
     assert_eq!(exec(&mut r), "PORTLAND, OR\n");
 }
 
+#[test]
+fn test_line_from_tokens() {
+    let line = Line::from_tokens(
+        Some(10),
+        vec![
+            Token::Word(Word::Print),
+            Token::Whitespace(1),
+            Token::Literal(Literal::Integer("1".to_string())),
+        ],
+    );
+    assert_eq!(line.to_string(), "10 PRINT 1");
+    assert!(line.ast().is_ok());
+    let mut r = Runtime::default();
+    r.enter(&line.to_string());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+}
+
+#[test]
+fn test_list_ranges() {
+    let mut r = Runtime::default();
+    for n in 1..=5 {
+        r.enter(&format!("{}0 PRINT {}", n, n));
+    }
+    r.enter(r#"LIST 20-40"#);
+    assert_eq!(exec(&mut r), "20 PRINT 2\n30 PRINT 3\n40 PRINT 4\n");
+    r.enter(r#"LIST -30"#);
+    assert_eq!(exec(&mut r), "10 PRINT 1\n20 PRINT 2\n30 PRINT 3\n");
+    r.enter(r#"LIST 30-"#);
+    assert_eq!(exec(&mut r), "30 PRINT 3\n40 PRINT 4\n50 PRINT 5\n");
+    r.enter(r#"LIST 30"#);
+    assert_eq!(exec(&mut r), "30 PRINT 3\n");
+}
+
+#[test]
+fn test_load_program() {
+    let mut r = Runtime::default();
+    let src = "10 A=6\n20 B=7\n30 PRINT A*B\n";
+    assert!(r.load_program(src).is_ok());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 42 \n");
+    let errors = r.load_program("10 PRINT 1\nPRINT 2\n").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].to_string(),
+        "?DIRECT STATEMENT IN FILE; In line 2 of the file."
+    );
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 42 \n");
+    assert!(r.load_program("10 PRINT A; _\nB\n").is_ok());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 0  0 \n");
+}
+
+#[derive(Default, Clone)]
+struct MemoryFileSystem(Rc<RefCell<HashMap<String, String>>>);
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, name: &str) -> Result<String, Error> {
+        match self.0.borrow().get(name) {
+            Some(data) => Ok(data.clone()),
+            None => Err(basic::error!(InternalError; "FILE NOT FOUND")),
+        }
+    }
+    fn write(&mut self, name: &str, data: &str) -> Result<(), Error> {
+        self.0
+            .borrow_mut()
+            .insert(name.to_string(), data.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_load_save_filesystem() {
+    let fs = MemoryFileSystem::default();
+    let mut r = Runtime::default();
+    r.set_filesystem(Some(Box::new(fs.clone())));
+    r.enter(r#"10 PRINT "HELLO""#);
+    r.enter(r#"SAVE "HELLO.BAS""#);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(fs.0.borrow()["HELLO.BAS"], "10 PRINT \"HELLO\"\n");
+    r.enter(r#"NEW"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"SAVE "EMPTY.BAS""#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    assert!(!fs.0.borrow().contains_key("EMPTY.BAS"));
+    r.enter(r#"LOAD "HELLO.BAS""#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 PRINT \"HELLO\"\n");
+    r.enter(r#"RUN "HELLO.BAS""#);
+    assert_eq!(exec(&mut r), "HELLO\n");
+    r.enter(r#"LOAD "NOPE.BAS""#);
+    assert_eq!(exec(&mut r), "?INTERNAL ERROR; FILE NOT FOUND\n");
+}
+
 #[test]
 fn test_new() {
     let mut r = Runtime::default();
@@ -282,6 +894,98 @@ fn test_on_gosub_invalid() {
     assert_eq!(exec(&mut r), " 30 \n");
 }
 
+#[test]
+fn test_error_with_code() {
+    let error = basic::error!(SyntaxError; "EXPECTED COMMA");
+    assert_eq!(error.code(), 2);
+    assert_eq!(
+        error.to_string_with_code(),
+        "Error 2: ?SYNTAX ERROR; EXPECTED COMMA"
+    );
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOTO 100"#);
+    r.enter(r#"RUN"#);
+    match r.execute(5000) {
+        Event::Errors(errors) => assert_eq!(
+            errors[0].to_string_with_code(),
+            "Error 8: ?UNDEFINED LINE IN 10:9"
+        ),
+        event => panic!("{:?}", event),
+    }
+}
+
+#[test]
+fn test_on_goto_expression() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 A=0:ON A+1 GOTO 100,200"#);
+    r.enter(r#"20 END"#);
+    r.enter(r#"100 PRINT 100:ON 1.9 GOTO 200,300"#);
+    r.enter(r#"200 PRINT 200:END"#);
+    r.enter(r#"300 PRINT 300"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 100 \n 200 \n");
+}
+
+#[test]
+fn test_program_size() {
+    let mut r = Runtime::default();
+    assert_eq!(r.line_count(), 0);
+    let (empty_ops, data) = r.program_size();
+    assert_eq!(data, 0);
+    r.enter(r#"10 DATA 1,2,3"#);
+    r.enter(r#"20 PRINT 1"#);
+    r.enter(r#"30 END"#);
+    assert_eq!(r.line_count(), 3);
+    let (ops, data) = r.program_size();
+    assert!(ops > empty_ops);
+    assert_eq!(data, 3);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    assert_eq!(r.program_size(), (ops, data));
+    r.register_function("CUBE", 1..=1, Box::new(|args| Ok(args[0].clone())))
+        .unwrap();
+    r.enter(r#"20 PRINT CUBE(1)"#);
+    let (ops, _) = r.program_size();
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    assert_eq!(r.program_size().0, ops);
+}
+
+#[test]
+fn test_statement_overflow() {
+    let mut r = Runtime::default();
+    r.enter(&format!("10 {}PRINT A", "A=1:".repeat(253)));
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    let mut program = Program::default();
+    program.codegen(&[Line::new(&format!("10 {}END", "A=1:".repeat(70000)))]);
+    let (_, indirect_errors, _) = program.link();
+    assert_eq!(
+        indirect_errors[0].to_string(),
+        "?OUT OF MEMORY IN 10:262144; STATEMENT OVERFLOW"
+    );
+}
+
+#[test]
+fn test_print_coalesced() {
+    let mut r = Runtime::default();
+    r.execute(1);
+    r.enter(r#"PRINT 1;2;"A";3"#);
+    match r.execute(5000) {
+        Event::Print(s) => assert_eq!(s, " 1  2 A 3 \nREADY.\n"),
+        event => panic!("{:?}", event),
+    }
+}
+
+#[test]
+fn test_print_adjacent_expressions() {
+    let mut r = Runtime::default();
+    r.enter(r#"PRINT 1 2"#);
+    assert_eq!(exec(&mut r), " 1  2 \n");
+    r.enter(r#"PRINT "A"2"B"(-3)"#);
+    assert_eq!(exec(&mut r), "A 2 B-3 \n");
+}
+
 #[test]
 fn test_read_data() {
     let mut r = Runtime::default();
@@ -292,6 +996,87 @@ fn test_read_data() {
     assert_eq!(exec(&mut r), " 99 Red Balloons\n");
 }
 
+#[test]
+fn test_read_data_coercion() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 READ A$,B$:PRINT A$;B$"#);
+    r.enter(r#"20 READ A"#);
+    r.enter(r#"30 DATA 3.14,-2"#);
+    r.enter(r#"40 DATA "PI""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "3.14-2\n?SYNTAX ERROR IN 40\n");
+}
+
+#[test]
+fn test_read_data_text() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 READ A$,B$,C$:PRINT A$;" ";B$;" ";C$"#);
+    r.enter(r#"20 DATA 1.50,1E3,&HFF"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "1.50 1E3 &HFF\n");
+}
+
+#[test]
+fn test_read_data_defstr() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DEFSTR A:READ A,AB:PRINT A;AB"#);
+    r.enter(r#"20 DATA "X",2.50"#);
+    r.enter(r#"30 DEFINT B:READ B:PRINT B"#);
+    r.enter(r#"40 DATA "Y""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "X2.50\n?SYNTAX ERROR IN 40\n");
+}
+
+#[test]
+fn test_read_out_of_data_line() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 READ A"#);
+    r.enter(r#"20 READ B"#);
+    r.enter(r#"30 DATA 1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?OUT OF DATA IN 20\n");
+}
+
+#[test]
+fn test_read_data_remark() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 READ A,B$:PRINT A;B$:READ C"#);
+    r.enter(r#"20 DATA 1,"2'X" 'ignored,3"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 2'X\n?OUT OF DATA IN 10\n");
+}
+
+#[test]
+fn test_read_data_spaces() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 READ A$:PRINT "[";A$;"]""#);
+    r.enter(r#"20 DATA " spaced ""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "[ spaced ]\n");
+    r.enter(r#"20 DATA hello world"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?SYNTAX ERROR IN 20:15; UNEXPECTED TOKEN\n");
+}
+
+#[test]
+fn test_replace_line() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 A=A+1"#);
+    r.enter(r#"20 PRINT "A=";A"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "A= 1 \n");
+    assert!(r.replace_line(r#"20 PRINT "NOW";A"#).is_ok());
+    r.enter(r#"GOTO 10"#);
+    assert_eq!(exec(&mut r), "NOW 2 \n");
+    assert!(r.replace_line("10").is_ok());
+    r.enter(r#"GOTO 20"#);
+    assert_eq!(exec(&mut r), "NOW 2 \n");
+    assert_eq!(
+        r.replace_line(r#"PRINT A"#).unwrap_err().to_string(),
+        "?ILLEGAL DIRECT"
+    );
+}
+
 #[test]
 fn test_restore_data() {
     let mut r = Runtime::default();
@@ -306,6 +1091,167 @@ fn test_restore_data() {
     assert_eq!(exec(&mut r), "-30 \n");
 }
 
+#[test]
+fn test_restore_data_after_edit() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DATA 1,2"#);
+    r.enter(r#"20 DATA 3"#);
+    r.enter(r#"30 DATA 4,5"#);
+    r.enter(r#"40 RESTORE 30:READ A:PRINT A"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 4 \n");
+    r.enter(r#"20 DATA 3,33,333"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 4 \n");
+    r.enter(r#"10"#);
+    r.enter(r#"RESTORE 30:READ A:PRINT A"#);
+    assert_eq!(exec(&mut r), " 4 \n");
+    r.enter(r#"30 DATA 7"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 7 \n");
+    r.enter(r#"RESTORE 20:READ A:PRINT A"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+}
+
+#[test]
+fn test_run_to_line() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 A=1"#);
+    r.enter(r#"20 B=2"#);
+    r.enter(r#"30 C=3"#);
+    r.enter(r#"40 PRINT A;B;C"#);
+    r.enter(r#"RUN"#);
+    r.run_to_line(30, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT A;B;C"#);
+    assert_eq!(exec(&mut r), " 1  2  0 \n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), " 1  2  3 \n");
+    r.enter(r#"RUN"#);
+    assert!(matches!(r.run_to_line(20, 1), Event::Running));
+    r.run_to_line(20, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT A;B;C"#);
+    assert_eq!(exec(&mut r), " 1  0  0 \n");
+}
+
+#[test]
+fn test_run_to_line_loop() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 X=X+1:IF X<3 THEN 10"#);
+    r.enter(r#"20 PRINT X"#);
+    r.enter(r#"RUN"#);
+    r.run_to_line(10, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT X"#);
+    assert_eq!(exec(&mut r), " 0 \n");
+    r.enter(r#"CONT"#);
+    r.run_to_line(10, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT X"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+    r.enter(r#"NEW"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"10 FOR I=1 TO 3:NEXT:GOTO 10"#);
+    r.enter(r#"RUN"#);
+    r.run_to_line(10, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"CONT"#);
+    r.run_to_line(10, 5000);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"PRINT I"#);
+    assert_eq!(exec(&mut r), " 4 \n");
+}
+
+#[test]
+fn test_call_stack() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOSUB 100:END"#);
+    r.enter(r#"100 FOR I=1 TO 5 STEP 2"#);
+    r.enter(r#"110 A=A+I"#);
+    r.enter(r#"120 NEXT:RETURN"#);
+    assert!(r.call_stack().is_empty());
+    r.enter(r#"RUN"#);
+    r.run_to_line(110, 5000);
+    assert_eq!(exec(&mut r), "");
+    assert_eq!(
+        r.call_stack(),
+        vec![
+            Frame::Gosub {
+                line_number: Some(10)
+            },
+            Frame::For {
+                var_name: "I".into(),
+                to: Val::Integer(5),
+                step: Val::Integer(2),
+                line_number: Some(100)
+            },
+        ]
+    );
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.call_stack().is_empty());
+}
+
+#[test]
+fn test_execute_for() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOTO 10"#);
+    r.enter(r#"RUN"#);
+    let start = std::time::Instant::now();
+    let event = r.execute_for(std::time::Duration::from_millis(20));
+    assert!(matches!(event, Event::Running));
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    r.interrupt();
+    assert_eq!(exec(&mut r), "?BREAK IN 10\n");
+    r.enter(r#"PRINT 1"#);
+    assert!(matches!(
+        r.execute_for(std::time::Duration::from_secs(2)),
+        Event::Print(s) if s.starts_with(" 1 \n")
+    ));
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 A=1"#);
+    r.enter(r#"20 FOR I=1 TO 3"#);
+    r.enter(r#"30 A=A*2:READ B:PRINT A;B"#);
+    r.enter(r#"40 NEXT"#);
+    r.enter(r#"50 DATA 5,6,7"#);
+    r.enter(r#"RUN"#);
+    r.run_to_line(30, 5000);
+    assert_eq!(exec(&mut r), "");
+    let snapshot = r.snapshot();
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), " 2  5 \n 4  6 \n 8  7 \n");
+    r.enter(r#"A=100:I=9"#);
+    assert_eq!(exec(&mut r), "");
+    r.restore_snapshot(snapshot.clone());
+    r.enter(r#"PRINT A;I"#);
+    assert_eq!(exec(&mut r), " 1  1 \n");
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), " 2  5 \n 4  6 \n 8  7 \n");
+    r.enter(r#"10 A=1"#);
+    r.restore_snapshot(snapshot);
+    r.enter(r#"CONT"#);
+    assert_eq!(exec(&mut r), "?CAN'T CONTINUE\n");
+}
+
+#[test]
+fn test_run_empty_program() {
+    let mut r = Runtime::default();
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "");
+    r.enter(r#"RUN"#);
+    assert!(matches!(r.execute(5000), Event::Print(s) if s == "READY.\n"));
+    assert!(matches!(r.execute(5000), Event::Stopped));
+    r.enter(r#"RUN "nonexistent""#);
+    assert!(matches!(r.execute(5000), Event::Run(s) if s == "nonexistent"));
+}
+
 #[test]
 fn test_swap() {
     let mut r = Runtime::default();
@@ -321,6 +1267,17 @@ fn test_swap() {
     assert_eq!(exec(&mut r), " 127 \n");
 }
 
+#[test]
+fn test_swap_array_element() {
+    let mut r = Runtime::default();
+    r.enter(r#"B(1)=5:A=9:SWAP A,B(1):PRINT A;B(1)"#);
+    assert_eq!(exec(&mut r), " 5  9 \n");
+    r.enter(r#"SWAP B(1),A:PRINT A;B(1)"#);
+    assert_eq!(exec(&mut r), " 9  5 \n");
+    r.enter(r#"B(2)=7:SWAP B(1),B(2):PRINT B(1);B(2)"#);
+    assert_eq!(exec(&mut r), " 7  5 \n");
+}
+
 #[test]
 fn test_tron_troff() {
     let mut r = Runtime::default();
@@ -336,6 +1293,121 @@ fn test_tron_troff() {
     );
 }
 
+#[test]
+fn test_tron_is_tracing() {
+    let mut r = Runtime::default();
+    assert!(!r.is_tracing());
+    r.enter(r#"TRON"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.is_tracing());
+    r.enter(r#"10 PRINT 1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "[10] 1 \n");
+    assert!(r.is_tracing());
+    r.enter(r#"TROFF"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(!r.is_tracing());
+    r.enter(r#"TRON:NEW"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(!r.is_tracing());
+}
+
+#[test]
+fn test_wait() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 PRINT 1:WAIT 1,0:PRINT 2"#);
+    r.enter(r#"RUN"#);
+    assert!(matches!(r.execute(5000), Event::Print(s) if s == " 1 \n"));
+    assert!(matches!(r.execute(5000), Event::Running));
+    assert!(matches!(r.execute(5000), Event::Running));
+    r.interrupt();
+    assert_eq!(exec(&mut r), "?BREAK IN 10\n");
+    r.enter(r#"WAIT 1,3,1:PRINT 3"#);
+    assert_eq!(exec(&mut r), " 3 \n");
+    r.enter(r#"WAIT 1,256"#);
+    assert_eq!(exec(&mut r), "?ILLEGAL FUNCTION CALL\n");
+    r.enter(r#"WAIT 1"#);
+    assert_eq!(exec(&mut r), "?SYNTAX ERROR; EXPECTED COMMA\n");
+}
+
+#[test]
+fn test_wait_names() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 WAITING=1:PRINT WAITING:WAIT1,1,1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n");
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut r = Runtime::default();
+    assert!(!r.undo());
+    r.enter(r#"10 PRINT 1"#);
+    r.enter(r#"20 PRINT 2"#);
+    r.enter(r#"10"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 2 \n");
+    assert!(r.undo());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n 2 \n");
+    assert!(r.redo());
+    assert!(!r.redo());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 2 \n");
+    assert!(r.undo());
+    r.enter(r#"30 PRINT 3"#);
+    assert!(!r.redo());
+    for _ in 0..60 {
+        r.enter(r#"40 END"#);
+    }
+    let mut count = 0;
+    while r.undo() {
+        count += 1;
+    }
+    assert_eq!(count, 50);
+}
+
+#[test]
+fn test_undo_while_running() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 GOTO 10"#);
+    r.enter(r#"20 END"#);
+    r.enter(r#"RUN"#);
+    assert!(matches!(r.execute(10), Event::Running));
+    assert!(!r.undo());
+    assert!(!r.redo());
+    r.interrupt();
+    assert_eq!(exec(&mut r), "?BREAK IN 10\n");
+    assert!(r.undo());
+    assert!(r.redo());
+}
+
+#[test]
+fn test_undo_delete_renum_new() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 PRINT 1"#);
+    r.enter(r#"20 PRINT 2"#);
+    r.enter(r#"30 PRINT 3"#);
+    r.enter(r#"DELETE 20"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.undo());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n 2 \n 3 \n");
+    r.enter(r#"RENUM 100"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.undo());
+    r.enter(r#"LIST"#);
+    assert_eq!(exec(&mut r), "10 PRINT 1\n20 PRINT 2\n30 PRINT 3\n");
+    r.enter(r#"NEW"#);
+    assert_eq!(exec(&mut r), "");
+    assert!(r.undo());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n 2 \n 3 \n");
+    assert!(r.redo());
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "");
+}
+
 #[test]
 fn test_while_wend_nested() {
     let mut r = Runtime::default();
@@ -364,3 +1436,93 @@ fn test_while_wend_not_nested() {
     r.enter(r#"RUN"#);
     assert_eq!(exec(&mut r), " 1  2  1  2 \n");
 }
+
+#[test]
+fn test_do_while_until() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DO WHILE I<3:I=I+1:PRINT I;:LOOP"#);
+    r.enter(r#"20 DO UNTIL J=2:J=J+1:PRINT J;:LOOP"#);
+    r.enter(r#"30 DO WHILE 0:PRINT "NO";:LOOP"#);
+    r.enter(r#"40 DO UNTIL 1:PRINT "NO";:LOOP"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1  2  3  1  2 \n");
+}
+
+#[test]
+fn test_loop_while_until() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DO:I=I+1:PRINT I;:LOOP WHILE I<3"#);
+    r.enter(r#"20 DO:J=J+1:PRINT J;:LOOP UNTIL J=2"#);
+    r.enter(r#"30 DO:PRINT "A";:LOOP WHILE 0"#);
+    r.enter(r#"40 DO:PRINT "B";:LOOP UNTIL 1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1  2  3  1  2 AB\n");
+}
+
+#[test]
+fn test_do_loop_names() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DOT=5:LOOPS=1:UNTILX=2:PRINT DOT;LOOPS;UNTILX"#);
+    r.enter(r#"20 IF DOT THENDO:DOT=DOT-2:LOOP UNTIL DOT<0"#);
+    r.enter(r#"30 PRINT DOT"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 5  1  2 \n-1 \n");
+}
+
+#[test]
+fn test_do_loop_nested() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 DO:I=I+1:J=0"#);
+    r.enter(r#"20 WHILE J<2:J=J+1:PRINT I*10+J;:WEND"#);
+    r.enter(r#"30 IF I=2 THEN 50"#);
+    r.enter(r#"40 LOOP"#);
+    r.enter(r#"50 PRINT "DONE""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 11  12  21  22 DONE\n");
+}
+
+#[test]
+fn test_do_loop_mismatch() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 LOOP"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?LOOP WITHOUT DO IN 10:4\n");
+    r.enter(r#"10 DO UNTIL 1"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?DO WITHOUT LOOP IN 10:4\n");
+    r.enter(r#"20 WEND"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(
+        exec(&mut r),
+        "?WEND WITHOUT WHILE IN 20:4\n?DO WITHOUT LOOP IN 10:4\n"
+    );
+}
+
+#[test]
+fn test_exit_while() {
+    let mut r = Runtime::default();
+    r.enter(r#"10 WHILE 1"#);
+    r.enter(r#"20 I=I+1:J=0"#);
+    r.enter(r#"30 WHILE 1:J=J+1:IF J>2 THEN EXIT WHILE"#);
+    r.enter(r#"40 PRINT I*10+J;:WEND"#);
+    r.enter(r#"50 IF I=2 THEN EXIT WHILE"#);
+    r.enter(r#"60 WEND"#);
+    r.enter(r#"70 PRINT "DONE""#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 11  12  21  22 DONE\n");
+    r.enter(r#"80 EXIT WHILE"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), "?WEND WITHOUT WHILE IN 80:4\n");
+}
+
+#[test]
+fn test_while_wend_type_mismatch() {
+    let mut r = Runtime::default();
+    r.enter(r#"WHILE "x":WEND"#);
+    assert_eq!(exec(&mut r), "?TYPE MISMATCH\n");
+    r.enter(r#"10 PRINT 1"#);
+    r.enter(r#"20 WHILE "x""#);
+    r.enter(r#"30 WEND"#);
+    r.enter(r#"RUN"#);
+    assert_eq!(exec(&mut r), " 1 \n?TYPE MISMATCH IN 20\n");
+}